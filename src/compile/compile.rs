@@ -12,20 +12,26 @@ use std::sync::Arc;
 // use std::time::{SystemTime, UNIX_EPOCH};
 
 use itertools::Itertools;
+use log::debug;
 use miette::{bail, ensure, Context, Diagnostic, Error, IntoDiagnostic, Result};
+use serde_json::json;
 use thiserror::Error;
 
 use crate::data::aggr::Aggregation;
+use crate::data::json::JsonValue;
 use crate::compile::expr::Expr;
 use super::program::{
-    FixedRuleArg, InputProgram, MagicAtom, MagicFixedRuleApply, MagicInlineRule, MagicRulesOrFixed, MagicSymbol, RelationOp, StratifiedMagicProgram
+    FixedRuleArg, InputAtom, InputInlineRule, InputInlineRulesOrFixed, InputProgram, InputRuleApplyAtom, MagicAtom, MagicFixedRuleApply, MagicFixedRuleRuleArg, MagicInlineRule, MagicRulesOrFixed, MagicSymbol, RelationOp, StratifiedMagicProgram, UnboundSymbolInRuleHead
 };
-use crate::compile::symb::Symbol;
+use super::stratify::StratificationInfo;
+use crate::compile::symb::{Symbol, PROG_ENTRY};
+use crate::data::relation::{ColumnDef, StoredRelationMetadata};
 use crate::data::value::DataValue;
-use crate::fixed_rule::{FixedRule, FixedRuleHandle};
-use crate::parse::{parse_script, CozoScript, SourceSpan};
+use crate::fixed_rule::{FixedRule, FixedRuleHandle, DEFAULT_FIXED_RULES};
+use crate::parse::{parse_expressions, parse_script, CozoScript, SourceSpan};
 use miette::Report;
 
+/// One stratum of a compiled script: the compiled rule sets, keyed by rule name.
 pub type CompiledProgram = BTreeMap<MagicSymbol, CompiledRuleSet>;
 // use crate::data::tuple::TupleT;
 //  use crate::data::{NamedRows, ValidityTs};
@@ -35,9 +41,12 @@ use crate::data::value::ValidityTs;
 // use crate::data::json::JsonValue;
 // use crate::query::ra::{InnerJoin, InlineFixedRA};
  
+ /// The compiled definition of a single rule.
  #[derive(Debug)]
  pub enum CompiledRuleSet {
+     /// Inline rules, one for each body given
      Rules(Vec<CompiledRule>),
+     /// An application of a fixed rule
      Fixed(MagicFixedRuleApply),
  }
  
@@ -84,6 +93,51 @@ use crate::data::value::ValidityTs;
          }
      }
  }
+
+/// The compiled rule sets reachable from the entry rule `?`, following the rules applied in
+/// each body and the in-memory inputs of fixed rules.
+fn reachable_rule_sets(compiled: &[CompiledProgram]) -> BTreeSet<&MagicSymbol> {
+    let all: BTreeMap<&MagicSymbol, &CompiledRuleSet> = compiled.iter().flatten().collect();
+    let mut stack: Vec<&MagicSymbol> = all
+        .keys()
+        .filter(|sym| sym.symbol().is_prog_entry())
+        .copied()
+        .collect();
+    let mut reached: BTreeSet<&MagicSymbol> = BTreeSet::new();
+    while let Some(sym) = stack.pop() {
+        if !reached.insert(sym) {
+            continue;
+        }
+        match all.get(sym) {
+            Some(CompiledRuleSet::Rules(rules)) => {
+                stack.extend(rules.iter().flat_map(|rule| rule.contained_rules.keys()))
+            }
+            Some(CompiledRuleSet::Fixed(fixed)) => {
+                stack.extend(fixed.rule_args.iter().filter_map(|arg| match arg {
+                    MagicFixedRuleRuleArg::InMem { name, .. } => Some(name),
+                    MagicFixedRuleRuleArg::Stored { .. } => None,
+                }))
+            }
+            None => {}
+        }
+    }
+    reached
+}
+
+/// Names of the compiled rules that cannot be reached from the entry rule `?`.
+pub(crate) fn unreachable_rules(compiled: &[CompiledProgram]) -> BTreeSet<String> {
+    let reached_names: BTreeSet<&str> = reachable_rule_sets(compiled)
+        .iter()
+        .map(|sym| sym.symbol().name.as_str())
+        .collect();
+    compiled
+        .iter()
+        .flat_map(|stratum| stratum.keys())
+        .map(|sym| sym.symbol().name.as_str())
+        .filter(|name| !reached_names.contains(name))
+        .map(|name| name.to_string())
+        .collect()
+}
  
  #[derive(Debug, Copy, Clone, Eq, PartialEq)]
  pub enum ContainedRuleMultiplicity {
@@ -93,10 +147,19 @@ use crate::data::value::ValidityTs;
  
  #[derive(Debug)]
  pub struct CompiledRule {
+     pub(crate) head: Vec<Symbol>,
      pub(crate) aggr: Vec<Option<(Aggregation, Vec<DataValue>)>>,
      pub(crate) relation: RelAlgebra,
      pub(crate) contained_rules: BTreeMap<MagicSymbol, ContainedRuleMultiplicity>,
  }
+
+ impl CompiledRule {
+     /// Names of the output columns of the rule, in the order the compiled relation
+     /// produces them.
+     pub fn head_names(&self) -> Vec<&str> {
+         self.head.iter().map(|symb| symb.name.as_str()).collect()
+     }
+ }
  
  #[derive(Debug, Error, Diagnostic)]
  #[error("Requested rule {0} not found")]
@@ -104,10 +167,10 @@ use crate::data::value::ValidityTs;
  struct RuleNotFound(String, #[label] SourceSpan);
  
  #[derive(Debug, Error, Diagnostic)]
- #[error("Arity mismatch for rule application {0}")]
+ #[error("Arity mismatch for {0} application {1}")]
  #[diagnostic(code(eval::rule_arity_mismatch))]
- #[diagnostic(help("Required arity: {1}, number of arguments given: {2}"))]
- struct ArityMismatch(String, usize, usize, #[label] SourceSpan);
+ #[diagnostic(help("Required arity of {0} {1}: {2}, number of arguments given: {3}"))]
+ struct ArityMismatch(&'static str, String, usize, usize, #[label] SourceSpan);
  
  #[derive(Debug, Copy, Clone, Eq, PartialEq)]
  pub enum IndexPositionUse {
@@ -123,6 +186,7 @@ use crate::data::value::ValidityTs;
      id: u16,
      name: String,
      arity: u8,
+     n_keys: usize,
      pub(crate) keys: Vec<ColumnDef>,
      pub(crate) non_keys: Vec<ColumnDef>,
  }
@@ -132,7 +196,7 @@ use crate::data::value::ValidityTs;
  
  pub struct Compiler {
      compiled_relations: HashMap<String, CompiledRelationHandle>,
-     fixed_rules: Vec<u16>,// TODO: type
+     fixed_rules: BTreeMap<String, Arc<Box<dyn FixedRule>>>,
      relations: HashMap<String, u16>, //TODO: type
      rules: HashMap<String, u16>,
  }
@@ -140,7 +204,50 @@ use crate::data::value::ValidityTs;
  #[derive(Debug, Diagnostic, Error)]
  #[error("Cannot create relation {0} as one with the same name already exists")]
  #[diagnostic(code(eval::rel_name_conflict))]
- struct CompiledRelNameConflictError(String);
+ struct CompiledRelNameConflictError(String, #[label] SourceSpan);
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Cannot find requested stored relation '{0}'")]
+#[diagnostic(code(query::relation_not_found))]
+struct StoredRelationNotFoundError(String);
+
+#[derive(Debug, Diagnostic, Error)]
+#[error("Required column {0} is not provided and has no default")]
+#[diagnostic(code(eval::required_col_not_provided))]
+struct ColumnNotProvidedError(String, #[label] SourceSpan);
+
+#[derive(Debug, Diagnostic, Error)]
+#[error("Stored relation name '{0}' is ambiguous when ignoring case")]
+#[diagnostic(code(query::relation_name_ambiguous))]
+#[diagnostic(help("Candidates are: {1}"))]
+struct AmbiguousRelationNameError(String, String);
+
+#[derive(Debug, Diagnostic, Error)]
+#[error("Cannot create relation {0} with no columns")]
+#[diagnostic(code(eval::rel_zero_arity))]
+#[diagnostic(help("A stored relation needs at least one key column"))]
+struct ZeroArityRelationError(String);
+
+#[derive(Debug, Diagnostic, Error)]
+#[error("Cannot create relation {0}: {1} key columns declared but the arity is {2}")]
+#[diagnostic(code(eval::rel_keys_exceed_arity))]
+struct RelationKeysExceedArityError(String, usize, u8);
+
+#[derive(Debug, Diagnostic, Error)]
+#[error("Cannot create relation {0} with {1} columns")]
+#[diagnostic(code(eval::rel_too_many_columns))]
+#[diagnostic(help("A stored relation can have at most {} columns", u8::MAX))]
+struct RelationTooWideError(String, usize);
+
+fn check_relation_schema(name: &str, n_keys: usize, arity: u8) -> Result<()> {
+    if arity == 0 {
+        bail!(ZeroArityRelationError(name.to_string()))
+    }
+    if n_keys > arity as usize {
+        bail!(RelationKeysExceedArityError(name.to_string(), n_keys, arity))
+    }
+    Ok(())
+}
  
  impl Compiler {
     pub(crate) fn relation_exists(&self, name: &str) -> bool {
@@ -181,10 +288,9 @@ use crate::data::value::ValidityTs;
                                             "error encountered when filling binding indices for {relation:#?}"
                                         )
                                     })?;
-
-                                    
-                                    println!("xxx145,header={header:?} relation=\n{relation:?}");
+                                    debug!("compiled rule {k:?} with head {header:?}:\n{relation:?}");
                                     collected.push(CompiledRule {
+                                        head: header.clone(),
                                         aggr: rule.aggr.clone(),
                                         relation,
                                         contained_rules: rule.contained_rules(),
@@ -194,6 +300,14 @@ use crate::data::value::ValidityTs;
                             }
 
                             MagicRulesOrFixed::Fixed { fixed } => {
+                                let input_arities: Vec<_> = fixed
+                                    .rule_args
+                                    .iter()
+                                    .map(|arg| self.fixed_rule_input_arity(arg, &store_arities))
+                                    .try_collect()?;
+                                fixed
+                                    .fixed_impl
+                                    .check_input_arities(&input_arities, fixed.arity, fixed.span)?;
                                 Ok((k, CompiledRuleSet::Fixed(fixed)))
                             }
                         }
@@ -201,9 +315,26 @@ use crate::data::value::ValidityTs;
                     .try_collect()
             })
             .try_collect()?;
-        println!("xxx164, compiled=\n{compiled:?}");
+        debug!("compiled program:\n{compiled:?}");
         Ok(compiled)
     }
+    /// The arity of an input relation of a fixed rule application, as known at compile time.
+    /// At run time, `FixedRuleInputRelation::arity` reports the same.
+    fn fixed_rule_input_arity(
+        &self,
+        arg: &MagicFixedRuleRuleArg,
+        store_arities: &BTreeMap<MagicSymbol, usize>,
+    ) -> Result<usize> {
+        Ok(match arg {
+            MagicFixedRuleRuleArg::InMem { name, .. } => {
+                *store_arities.get(name).ok_or_else(|| {
+                    RuleNotFound(name.symbol().to_string(), name.symbol().span)
+                })?
+            }
+            MagicFixedRuleRuleArg::Stored { name, .. } => self.get_relation(name)?.arity as usize,
+        })
+    }
+
     pub(crate) fn compile_magic_rule_body(
         &self,
         rule: &MagicInlineRule,
@@ -219,6 +350,30 @@ use crate::data::value::ValidityTs;
             serial_id += 1;
             ret
         };
+        // Normalization replaces a variable repeated within one atom, e.g. `rel[x, x]`,
+        // by a generated symbol unified with the original. Remember these so that the
+        // resulting joins can be reported as self-joins. Equalities written by the user
+        // bind their own symbols and are not aliases.
+        let aliases: BTreeMap<&Symbol, &Symbol> = rule
+            .body
+            .iter()
+            .filter_map(|atom| match atom {
+                MagicAtom::Unification(u)
+                    if !u.one_many_unif && u.binding.is_generated_symbol() =>
+                {
+                    match &u.expr {
+                        Expr::Binding { var, .. } => Some((&u.binding, var)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+        // a join is a self-join when it matches both a repeated variable and its alias
+        let joins_repeated_var = |keys: &[Symbol]| {
+            keys.iter()
+                .any(|k| aliases.get(k).is_some_and(|orig| keys.contains(orig)))
+        };
         for atom in &rule.body {
             match atom {
                 MagicAtom::Rule(rule_app) => {
@@ -232,6 +387,7 @@ use crate::data::value::ValidityTs;
                     ensure!(
                         *store_arity == rule_app.args.len(),
                         ArityMismatch(
+                            "rule",
                             rule_app.name.symbol().to_string(),
                             *store_arity,
                             rule_app.args.len(),
@@ -256,14 +412,24 @@ use crate::data::value::ValidityTs;
 
                     let right =
                         RelAlgebra::derived(right_vars, rule_app.name.clone(), rule_app.span);
-                    debug_assert_eq!(prev_joiner_vars.len(), right_joiner_vars.len());
-                    ret = ret.join(right, prev_joiner_vars, right_joiner_vars, rule_app.span);
+                    let is_cartesian = prev_joiner_vars.is_empty() && !ret.is_unit();
+                    let is_self_join = joins_repeated_var(&prev_joiner_vars);
+                    ret = ret.try_join(right, prev_joiner_vars, right_joiner_vars, rule_app.span)?;
+                    if is_cartesian {
+                        debug!("rule application {:?} shares no variables with the atoms before it, compiled as a cartesian join", rule_app.name);
+                        ret.mark_cartesian_join();
+                    }
+                    if is_self_join {
+                        debug!("rule application {:?} joins on a repeated variable, compiled as a self-join", rule_app.name);
+                        ret.mark_self_join();
+                    }
                 }
                 MagicAtom::Relation(rel_app) => {
                     let store = self.get_relation(&rel_app.name)?;
                     ensure!(
                         store.arity as usize == rel_app.args.len(),
                         ArityMismatch(
+                            "relation",
                             rel_app.name.to_string(),
                             store.arity as usize,
                             rel_app.args.len(),
@@ -309,10 +475,20 @@ use crate::data::value::ValidityTs;
                         right_vars,
                         rel_app.span,
                         name,
+                        store.n_keys,
                     )?;
-                    debug_assert_eq!(prev_joiner_vars.len(), right_joiner_vars.len());
+                    let is_cartesian = prev_joiner_vars.is_empty() && !ret.is_unit();
+                    let is_self_join = joins_repeated_var(&prev_joiner_vars);
                     ret =
-                        ret.join(right, prev_joiner_vars, right_joiner_vars, rel_app.span);
+                        ret.try_join(right, prev_joiner_vars, right_joiner_vars, rel_app.span)?;
+                    if is_cartesian {
+                        debug!("relation application {} shares no variables with the atoms before it, compiled as a cartesian join", rel_app.name);
+                        ret.mark_cartesian_join();
+                    }
+                    if is_self_join {
+                        debug!("relation application {} joins on a repeated variable, compiled as a self-join", rel_app.name);
+                        ret.mark_self_join();
+                    }
                 }
                 MagicAtom::Predicate(p) => {
                     ret = ret.filter(p.clone())?;
@@ -363,14 +539,6 @@ use crate::data::value::ValidityTs;
         }
 
         let cur_ret_set: BTreeSet<_> = ret.bindings_after_eliminate().into_iter().collect();
-        #[derive(Debug, Error, Diagnostic)]
-        #[error("Symbol '{0}' in rule head is unbound")]
-        #[diagnostic(code(eval::unbound_symb_in_head))]
-        #[diagnostic(help(
-            "Note that symbols occurring only in negated positions are not considered bound"
-        ))]
-        struct UnboundSymbolInRuleHead(String, #[label] SourceSpan);
-
         ensure!(cur_ret_set == ret_vars_set, {
             let unbound = ret_vars_set.difference(&cur_ret_set).next().unwrap();
             UnboundSymbolInRuleHead(unbound.to_string(), unbound.span)
@@ -386,12 +554,14 @@ use crate::data::value::ValidityTs;
     pub(crate) fn create_relation(
         &mut self,
         name: String,
+        n_keys: usize,
         arity: u8,
+        span: SourceSpan,
     ) -> Result<CompiledRelationHandle> {
-
+        check_relation_schema(&name, n_keys, arity)?;
 
         if self.compiled_relations.contains_key(&name) {
-            bail!(CompiledRelNameConflictError(name))
+            bail!(CompiledRelNameConflictError(name, span))
         };
 
         let id = self.compiled_relations.len() as u16;
@@ -401,6 +571,7 @@ use crate::data::value::ValidityTs;
             name,
             id,
             arity,
+            n_keys,
             keys: vec![],
             non_keys: vec![]
         };
@@ -411,12 +582,30 @@ use crate::data::value::ValidityTs;
         Ok(meta)
     }
 
-    pub(crate) fn get_relation(&self, name: &str) -> Result<CompiledRelationHandle> {
-        #[derive(Error, Diagnostic, Debug)]
-        #[error("Cannot find requested stored relation '{0}'")]
-        #[diagnostic(code(query::relation_not_found))]
-        struct StoredRelationNotFoundError(String);
+    /// Like `create_relation`, but an existing relation of the same name has its
+    /// schema replaced instead of causing a conflict.
+    pub(crate) fn replace_relation(
+        &mut self,
+        name: String,
+        n_keys: usize,
+        arity: u8,
+        span: SourceSpan,
+    ) -> Result<CompiledRelationHandle> {
+        check_relation_schema(&name, n_keys, arity)?;
+
+        match self.compiled_relations.get_mut(&name) {
+            None => self.create_relation(name, n_keys, arity, span),
+            Some(existing) => {
+                existing.arity = arity;
+                existing.n_keys = n_keys;
+                Ok(existing.clone())
+            }
+        }
+    }
 
+    /// Look up the stored relation `name`. Names are compared case-sensitively,
+    /// see [Compiler::get_relation_ci] for a case-insensitive lookup.
+    pub(crate) fn get_relation(&self, name: &str) -> Result<CompiledRelationHandle> {
         let found = self.compiled_relations
             .get(name)
             .cloned()
@@ -424,6 +613,43 @@ use crate::data::value::ValidityTs;
 
         Ok(found?)
     }
+
+    /// Look up the stored relation `name` ignoring case. A relation named exactly `name` is
+    /// always preferred. Otherwise, fails if several stored relations differ from `name`
+    /// only by case, as there is no way to pick one of them.
+    pub(crate) fn get_relation_ci(&self, name: &str) -> Result<CompiledRelationHandle> {
+        if let Some(handle) = self.compiled_relations.get(name) {
+            return Ok(handle.clone());
+        }
+        let lowered = name.to_lowercase();
+        let candidates = self
+            .compiled_relations
+            .iter()
+            .filter(|(k, _)| k.to_lowercase() == lowered)
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect_vec();
+        match candidates.as_slice() {
+            [] => bail!(StoredRelationNotFoundError(name.to_string())),
+            [(_, handle)] => Ok((*handle).clone()),
+            _ => bail!(AmbiguousRelationNameError(
+                name.to_string(),
+                candidates.iter().map(|(k, _)| k.as_str()).join(", ")
+            )),
+        }
+    }
+
+    /// The name under which the stored relation `name` was created, matching names
+    /// ignoring case as [Compiler::get_relation_ci] does.
+    pub fn relation_name_ci(&self, name: &str) -> Result<String> {
+        Ok(self.get_relation_ci(name)?.name)
+    }
+
+    /// The arity of the stored relation `name`, or `None` if it does not exist.
+    pub fn relation_arity(&self, name: &str) -> Option<usize> {
+        self.compiled_relations
+            .get(name)
+            .map(|handle| handle.arity as usize)
+    }
  
  }
  
@@ -496,6 +722,8 @@ use crate::data::value::ValidityTs;
      pub(crate) filters: Vec<Expr>,
      pub(crate) span: SourceSpan,
      pub(crate) name: String,
+     /// Number of leading bindings that are key columns of the stored relation
+     pub(crate) n_keys: usize,
  }
  
  #[derive(Debug, Clone)]
@@ -505,6 +733,10 @@ use crate::data::value::ValidityTs;
      pub(crate) joiner: Joiner,
      pub(crate) to_eliminate: BTreeSet<Symbol>,
      pub(crate) span: SourceSpan,
+     /// Set when the join matches a variable repeated within the right atom, e.g. `a[x], rel[x, x]`
+     pub(crate) self_join: bool,
+     /// Set when the two sides share no variables, so every pair of rows is produced
+     pub(crate) cartesian: bool,
  }
  
  #[derive(Debug, Clone)]
@@ -540,6 +772,7 @@ use crate::data::value::ValidityTs;
          right_keys: Vec<Symbol>,
          span: SourceSpan,
      ) -> Self {
+         debug_assert_eq!(left_keys.len(), right_keys.len());
          RelAlgebra::Join(Box::new(InnerJoin {
              left: self,
              right,
@@ -549,8 +782,35 @@ use crate::data::value::ValidityTs;
              },
              to_eliminate: Default::default(),
              span,
+             self_join: false,
+             cartesian: false,
          }))
      }
+
+     /// Like `join`, but reports mismatched join keys as an error instead of
+     /// leaving them to panic later in `Joiner::join_indices`.
+     pub(crate) fn try_join(
+         self,
+         right: RelAlgebra,
+         left_keys: Vec<Symbol>,
+         right_keys: Vec<Symbol>,
+         span: SourceSpan,
+     ) -> Result<Self> {
+         let joiner = Joiner::new(left_keys, right_keys, span)?;
+         Ok(self.join(right, joiner.left_keys, joiner.right_keys, span))
+     }
+
+     pub(crate) fn mark_self_join(&mut self) {
+         if let RelAlgebra::Join(inner) = self {
+             inner.self_join = true;
+         }
+     }
+
+     pub(crate) fn mark_cartesian_join(&mut self) {
+         if let RelAlgebra::Join(inner) = self {
+             inner.cartesian = true;
+         }
+     }
  
      pub(crate) fn reorder(self, new_order: Vec<Symbol>) -> Self {
          Self::Reorder(ReorderRA {
@@ -570,6 +830,74 @@ use crate::data::value::ValidityTs;
          }
      }
  
+     /// The columns this node produces, in order, after dropping the variables that are
+     /// not needed further up the plan.
+     pub(crate) fn output_bindings(&self) -> Vec<Symbol> {
+         self.bindings_after_eliminate()
+     }
+ 
+     /// The tree of this node and the nodes below it as JSON. As in `explain`, a join whose
+     /// left side is the unit relation is shown as just its right side.
+     pub(crate) fn to_json(&self) -> JsonValue {
+         let exprs = |exprs: &[Expr]| exprs.iter().map(|e| e.to_string()).collect_vec();
+         let mut node = match self {
+             RelAlgebra::Fixed(f) => json!({"op": "fixed", "rows": f.data.len()}),
+             RelAlgebra::TempStore(t) => json!({
+                 "op": "load_mem",
+                 "ref": t.storage_key.to_string(),
+                 "filters": exprs(&t.filters),
+             }),
+             RelAlgebra::Stored(s) => json!({
+                 "op": "load_stored",
+                 "ref": format!(":{}", s.name),
+                 "filters": exprs(&s.filters),
+             }),
+             RelAlgebra::Join(j) => {
+                 if j.left.is_unit() {
+                     return j.right.to_json();
+                 }
+                 let op = if j.self_join {
+                     "self_join"
+                 } else if j.cartesian {
+                     "cartesian_join"
+                 } else {
+                     j.join_type()
+                 };
+                 json!({
+                     "op": op,
+                     "joins_on": j.joiner.as_map(),
+                     "left": j.left.to_json(),
+                     "right": j.right.to_json(),
+                 })
+             }
+             RelAlgebra::Reorder(r) => json!({
+                 "op": "reorder",
+                 "new_order": r.new_order.iter().map(|s| s.to_string()).collect_vec(),
+                 "parent": r.relation.to_json(),
+             }),
+             RelAlgebra::Filter(f) => json!({
+                 "op": "filter",
+                 "filters": exprs(&f.filters),
+                 "parent": f.parent.to_json(),
+             }),
+             RelAlgebra::Unification(u) => json!({
+                 "op": if u.is_multi { "multi-unify" } else { "unify" },
+                 "binding": u.binding.to_string(),
+                 "expr": u.expr.to_string(),
+                 "parent": u.parent.to_json(),
+             }),
+         };
+         node["bindings"] = json!(self
+             .bindings_after_eliminate()
+             .iter()
+             .map(|s| s.to_string())
+             .collect_vec());
+         if let Some(eliminated) = self.eliminate_set() {
+             node["eliminated"] = json!(eliminated.iter().map(|s| s.to_string()).collect_vec());
+         }
+         node
+     }
+
      fn bindings_before_eliminate(&self) -> Vec<Symbol> {
          match self {
              RelAlgebra::Fixed(f) => f.bindings.clone(),
@@ -586,7 +914,7 @@ use crate::data::value::ValidityTs;
          }
      }
  
-     fn eliminate_set(&self) -> Option<&BTreeSet<Symbol>> {
+     pub(crate) fn eliminate_set(&self) -> Option<&BTreeSet<Symbol>> {
          match self {
              RelAlgebra::Fixed(r) => Some(&r.to_eliminate),
              RelAlgebra::TempStore(_) => None,
@@ -656,6 +984,7 @@ use crate::data::value::ValidityTs;
                  mut filters,
                  span,
                  name,
+                 n_keys,
              }) => {
                  filters.push(filter);
                  RelAlgebra::Stored(StoredRA {
@@ -663,6 +992,7 @@ use crate::data::value::ValidityTs;
                      filters,
                      span,
                      name,
+                     n_keys,
                  })
              }
              RelAlgebra::Join(inner) => {
@@ -681,7 +1011,8 @@ use crate::data::value::ValidityTs;
                      joiner,
                      to_eliminate,
                      span,
-                     ..
+                     self_join,
+                     cartesian,
                  } = *inner;
                  for filter in filters {
                      let f_bindings = filter.bindings()?;
@@ -699,6 +1030,8 @@ use crate::data::value::ValidityTs;
                      joiner,
                      to_eliminate,
                      span,
+                     self_join,
+                     cartesian,
                  }));
                  if !remaining.is_empty() {
                      joined = RelAlgebra::Filter(FilteredRA {
@@ -733,12 +1066,14 @@ use crate::data::value::ValidityTs;
          bindings: Vec<Symbol>,
          span: SourceSpan,
          name: String,
+         n_keys: usize,
      ) -> Result<Self> {
          Ok(Self::Stored(StoredRA {
              bindings,
              filters: vec![],
              span,
              name,
+             n_keys,
          }))
      }
  
@@ -786,6 +1121,16 @@ use crate::data::value::ValidityTs;
  }
  
  impl InlineFixedRA {
+     /// The symbols bound by each column of the rows.
+     pub(crate) fn bindings(&self) -> &[Symbol] {
+         &self.bindings
+     }
+
+     /// The constant rows of the relation.
+     pub(crate) fn rows(&self) -> &[Vec<DataValue>] {
+         &self.data
+     }
+
      pub(crate) fn unit(span: SourceSpan) -> Self {
          Self {
              bindings: vec![],
@@ -900,36 +1245,6 @@ use crate::data::value::ValidityTs;
      }
  }
  
- #[derive(Debug, Clone, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
- pub(crate) struct ColumnDef {
-     pub(crate) name: String,
-     pub(crate) typing: NullableColType,
-     pub(crate) default_gen: Option<Expr>,
- }
- 
- #[derive(Debug, Clone, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
- pub enum ColType {
-     Any,
-     Bool,
-     Int,
-     Float,
-     String,
-     Bytes,
-     Uuid,
-     List {
-         eltype: Box<NullableColType>,
-         len: Option<usize>,
-     },
-     Tuple(Vec<NullableColType>),
-     Validity,
-     Json,
- }
- 
- #[derive(Debug, Clone, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
- pub struct NullableColType {
-     pub coltype: ColType,
-     pub nullable: bool,
- }
  
  impl StoredRA {
      fn fill_binding_indices_and_compile(&mut self) -> Result<()> {
@@ -967,19 +1282,37 @@ use crate::data::value::ValidityTs;
     pub fn new() -> Self {
         Compiler {
             compiled_relations: HashMap::new(),
-            fixed_rules: Vec::new(),
+            fixed_rules: DEFAULT_FIXED_RULES.clone(),
             relations: HashMap::new(),
             rules: HashMap::new(),
         }
     }
 
+    /// Make the fixed rule `rule` available to scripts compiled afterwards under `name`,
+    /// replacing any fixed rule previously registered with that name.
+    pub fn register_fixed_rule(&mut self, name: String, rule: Arc<Box<dyn FixedRule>>) {
+        self.fixed_rules.insert(name, rule);
+    }
+
+    /// Forget all relations and rules, returning to the state of `Compiler::new()`
+    /// while keeping the allocated capacity. Fixed rules added with
+    /// [Compiler::register_fixed_rule] are dropped and only the default ones remain.
+    pub fn clear(&mut self) {
+        self.compiled_relations.clear();
+        self.fixed_rules = DEFAULT_FIXED_RULES.clone();
+        self.relations.clear();
+        self.rules.clear();
+    }
+
     fn do_compile_script(
         &mut self,
         payload: &str,
+        params: &BTreeMap<String, DataValue>,
     ) -> Result<Vec<BTreeMap<MagicSymbol, CompiledRuleSet>>> {
         match parse_script(
             payload,
-            &BTreeMap::new(),
+            params,
+            &self.fixed_rules,
         )? {
             CozoScript::Single(p) => self.compile_single(p),
             _ => todo!("it's a bug")
@@ -1015,10 +1348,112 @@ use crate::data::value::ValidityTs;
         Ok(compiled)
     }
 
+    fn set_relation_columns(&mut self, name: &str, metadata: &StoredRelationMetadata) {
+        if let Some(handle) = self.compiled_relations.get_mut(name) {
+            handle.keys = metadata.keys.clone();
+            handle.non_keys = metadata.non_keys.clone();
+        }
+    }
+
+    /// Make a `:put` that omits columns of the stored relation produce them anyway: the entry
+    /// rule is wrapped in one that binds each omitted column to its default, or to null if
+    /// the column has no default but is nullable.
+    fn fill_omitted_columns(&self, input_program: &mut InputProgram) -> Result<()> {
+        let Some((handle, _, _)) = &input_program.out_opts.store_relation else {
+            return Ok(());
+        };
+        let Some(stored) = self.compiled_relations.get(&handle.name.name) else {
+            return Ok(());
+        };
+        let span = handle.span;
+        let provided: BTreeSet<&str> = handle
+            .metadata
+            .keys
+            .iter()
+            .chain(handle.metadata.non_keys.iter())
+            .map(|col| col.name.as_str())
+            .collect();
+        let mut omitted = vec![];
+        let stored_cols = stored.keys.iter().map(|col| (col, true));
+        for (col, is_key) in stored_cols.chain(stored.non_keys.iter().map(|col| (col, false))) {
+            if provided.contains(col.name.as_str()) {
+                continue;
+            }
+            let expr = match &col.default_gen {
+                Some(expr) => expr.clone(),
+                None if col.typing.nullable => Expr::Const {
+                    val: DataValue::Null,
+                    span,
+                },
+                None => bail!(ColumnNotProvidedError(col.name.clone(), span)),
+            };
+            omitted.push((col.clone(), is_key, expr));
+        }
+        if omitted.is_empty() {
+            return Ok(());
+        }
+
+        let head = input_program.get_entry_out_head()?;
+        let entry = Symbol::new(PROG_ENTRY, SourceSpan(0, 0));
+        let inner = Symbol::new("*put_input", span);
+        let original = input_program.prog.remove(&entry).unwrap();
+        input_program.prog.insert(inner.clone(), original);
+
+        let mut out_head = head.clone();
+        let mut body = vec![InputAtom::Rule {
+            inner: InputRuleApplyAtom {
+                name: inner,
+                args: head
+                    .into_iter()
+                    .map(|var| Expr::Binding {
+                        var,
+                        tuple_pos: None,
+                    })
+                    .collect(),
+                span,
+            },
+        }];
+        let Some((handle, _, _)) = &mut input_program.out_opts.store_relation else {
+            unreachable!()
+        };
+        for (col, is_key, expr) in omitted {
+            let binding = Symbol::new(col.name.clone(), span);
+            body.push(InputAtom::Unification {
+                inner: super::program::Unification {
+                    binding: binding.clone(),
+                    expr,
+                    one_many_unif: false,
+                    span,
+                },
+            });
+            out_head.push(binding.clone());
+            if is_key {
+                handle.metadata.keys.push(col);
+                handle.key_bindings.push(binding);
+            } else {
+                handle.metadata.non_keys.push(col);
+                handle.dep_bindings.push(binding);
+            }
+        }
+        let aggr = out_head.iter().map(|_| None).collect();
+        input_program.prog.insert(
+            entry,
+            InputInlineRulesOrFixed::Rules {
+                rules: vec![InputInlineRule {
+                    head: out_head,
+                    aggr,
+                    body,
+                    span,
+                }],
+            },
+        );
+        Ok(())
+    }
+
     /// This is the entry to query evaluation
     pub(crate) fn compile_query(
         &mut self,
-        input_program: InputProgram,
+        mut input_program: InputProgram,
         callback_targets: &BTreeSet<String>,
         top_level: bool,
     ) -> Result<Vec<BTreeMap<MagicSymbol, CompiledRuleSet>>> {
@@ -1026,21 +1461,35 @@ use crate::data::value::ValidityTs;
 
         // Some checks in case the query specifies mutation
         if let Some((meta, op, _)) = &input_program.out_opts.store_relation {
-            if *op == RelationOp::Create {
-                #[derive(Debug, Error, Diagnostic)]
-                #[error("Stored relation {0} conflicts with an existing one")]
-                #[diagnostic(code(eval::stored_relation_conflict))]
-                struct StoreRelationConflict(String);
-
-                ensure!(
-                    !self.relation_exists(&meta.name),
-                    StoreRelationConflict(meta.name.to_string())
-                );
-
-                let arity = meta.metadata.keys.len() as u8; // TODO: ronen - not sure this is the arity of the relation, check latedr
-                self.create_relation(meta.name.name.to_string(), arity)?;
+            let n_keys = meta.metadata.keys.len();
+            let n_cols = n_keys + meta.metadata.non_keys.len();
+            let arity = u8::try_from(n_cols)
+                .map_err(|_| RelationTooWideError(meta.name.name.to_string(), n_cols))?;
+            match op {
+                RelationOp::Create => {
+                    #[derive(Debug, Error, Diagnostic)]
+                    #[error("Stored relation {0} conflicts with an existing one")]
+                    #[diagnostic(code(eval::stored_relation_conflict))]
+                    struct StoreRelationConflict(String);
+
+                    ensure!(
+                        !self.relation_exists(&meta.name),
+                        StoreRelationConflict(meta.name.to_string())
+                    );
+
+                    self.create_relation(meta.name.name.to_string(), n_keys, arity, meta.name.span)?;
+                    self.set_relation_columns(&meta.name.name, &meta.metadata);
+                }
+                RelationOp::Replace => {
+                    self.replace_relation(meta.name.name.to_string(), n_keys, arity, meta.name.span)?;
+                    self.set_relation_columns(&meta.name.name, &meta.metadata);
+                }
+                _ => {}
             }
         };
+        if matches!(input_program.out_opts.store_relation, Some((_, RelationOp::Put, _))) {
+            self.fill_omitted_columns(&mut input_program)?;
+        }
 
         // query compilation
         let entry_head_or_default = input_program.get_entry_out_head_or_default()?;
@@ -1053,18 +1502,152 @@ use crate::data::value::ValidityTs;
 
     }
  
-     /// Compile the CozoScript passed in. The `params` argument is a map of parameters.
+     /// Compile the CozoScript passed in.
      pub fn compile_script(
          &mut self,
          payload: &str,
      ) -> Result<Vec<BTreeMap<MagicSymbol, CompiledRuleSet>>> {
-        let params: BTreeMap<String, DataValue> = BTreeMap::new();
-        println!("xxx404");
+        self.compile_script_with_params(payload, BTreeMap::new())
+     }
+
+     /// Compile the CozoScript passed in. The `params` argument is a map of parameters,
+     /// referred to as `$name` in the script.
+     pub fn compile_script_with_params(
+         &mut self,
+         payload: &str,
+         params: BTreeMap<String, DataValue>,
+     ) -> Result<Vec<BTreeMap<MagicSymbol, CompiledRuleSet>>> {
          self.do_compile_script(
              payload,
+             &params,
          )
      }
 
+     /// Evaluate the standalone expression `expr_src` to a constant. The `params` argument
+     /// is a map of parameters, referred to as `$name` in the expression.
+     pub fn eval_const_expression(
+         &self,
+         expr_src: &str,
+         params: &BTreeMap<String, DataValue>,
+     ) -> Result<DataValue> {
+         parse_expressions(expr_src, params)?.eval_to_const()
+     }
+
+     /// Check that `payload` parses and passes the checks made while normalizing, stratifying
+     /// and rewriting it, without compiling the resulting program. The `params` argument is
+     /// a map of parameters, referred to as `$name` in the script.
+     pub fn validate_script(
+         &self,
+         payload: &str,
+         params: &BTreeMap<String, DataValue>,
+     ) -> Result<()> {
+         let input_program = match parse_script(payload, params, &self.fixed_rules)? {
+             CozoScript::Single(p) => p,
+             _ => bail!("only single query scripts can be validated"),
+         };
+         let (normalized_program, _) = input_program.into_normalized_program(self)?;
+         normalized_program.ensure_heads_bound()?;
+         let (stratified_program, _) = normalized_program.into_stratified_program()?;
+         stratified_program.magic_sets_rewrite(self)?;
+         Ok(())
+     }
+
+     /// Describe how the query in `payload` is stratified, without compiling it further.
+     /// The `params` argument is a map of parameters, referred to as `$name` in the script.
+     pub fn stratify_script(
+         &self,
+         payload: &str,
+         params: &BTreeMap<String, DataValue>,
+     ) -> Result<StratificationInfo> {
+         let input_program = match parse_script(payload, params, &self.fixed_rules)? {
+             CozoScript::Single(p) => p,
+             _ => bail!("only single query scripts can be stratified"),
+         };
+         let (normalized_program, _) = input_program.into_normalized_program(self)?;
+         let dependencies = normalized_program.dependencies();
+         let (stratified_program, _) = normalized_program.into_stratified_program()?;
+         Ok(stratified_program.describe(dependencies))
+     }
+
+     /// Compile the CozoScript passed in, also returning the names of the rules it defines
+     /// that are never used, directly or indirectly, by the entry rule `?`. The `params`
+     /// argument is a map of parameters, referred to as `$name` in the script.
+     pub fn compile_script_reporting_unused(
+         &mut self,
+         payload: &str,
+         params: &BTreeMap<String, DataValue>,
+     ) -> Result<(Vec<CompiledProgram>, Vec<String>)> {
+         let input_program = match parse_script(payload, params, &self.fixed_rules)? {
+             CozoScript::Single(p) => p,
+             _ => bail!("only single query scripts can be checked for unused rules"),
+         };
+         let defined: BTreeSet<String> = input_program
+             .prog
+             .keys()
+             .filter(|sym| !sym.is_prog_entry())
+             .map(|sym| sym.name.to_string())
+             .collect();
+         let compiled = self.compile_single(input_program)?;
+         // stratification already drops rules the entry never touches, so anything defined
+         // but absent from the compiled program is unused as well
+         let present: BTreeSet<String> = compiled
+             .iter()
+             .flat_map(|prog| prog.keys().map(|sym| sym.symbol().name.to_string()))
+             .collect();
+         let mut unused = unreachable_rules(&compiled);
+         unused.extend(defined.difference(&present).cloned());
+         Ok((compiled, unused.into_iter().collect()))
+     }
+
+     /// The compiled program as JSON: one array per stratum, holding an object for each
+     /// rule set with the relational algebra tree of each of its rules.
+     pub fn compiled_to_json(strata: &[CompiledProgram]) -> Result<JsonValue> {
+         Ok(JsonValue::Array(
+             strata
+                 .iter()
+                 .map(|stratum| {
+                     stratum
+                         .iter()
+                         .map(|(name, rule_set)| match rule_set {
+                             CompiledRuleSet::Rules(rules) => json!({
+                                 "name": name.to_string(),
+                                 "kind": "rules",
+                                 "arity": rule_set.arity(),
+                                 "rules": rules.iter().map(|rule| json!({
+                                     "head": rule.head_names(),
+                                     "aggr": rule.aggr.iter()
+                                         .map(|a| a.as_ref().map(|(aggr, _)| aggr.name))
+                                         .collect_vec(),
+                                     "relation": rule.relation.to_json(),
+                                 })).collect_vec(),
+                             }),
+                             CompiledRuleSet::Fixed(fixed) => json!({
+                                 "name": name.to_string(),
+                                 "kind": "fixed",
+                                 "arity": rule_set.arity(),
+                                 "fixed_rule": fixed.fixed_handle.name.to_string(),
+                             }),
+                         })
+                         .collect()
+                 })
+                 .collect(),
+         ))
+     }
+
+     /// The number of strata in a compiled program.
+     pub fn stratum_count(strata: &[CompiledProgram]) -> usize {
+         strata.len()
+     }
+
+     /// The names of the rules compiled into stratum `idx`, as displayed in `explain`,
+     /// or nothing if there is no such stratum.
+     pub fn rule_names_in_stratum(strata: &[CompiledProgram], idx: usize) -> Vec<String> {
+         strata
+             .get(idx)
+             .map(|stratum| stratum.keys().map(|sym| sym.to_string()).collect())
+             .unwrap_or_default()
+     }
+
  }
 
  #[derive(Debug)]
@@ -1145,7 +1728,28 @@ pub(crate) struct NormalFormRuleApplyAtom {
 
 
 
+#[derive(Debug, Diagnostic, Error)]
+#[error("Cannot join on {0} left keys against {1} right keys")]
+#[diagnostic(code(eval::join_keys_mismatch))]
+#[diagnostic(help("This is a bug in the compiler, please report it"))]
+struct JoinKeysMismatchError(usize, usize, #[label] SourceSpan);
+
 impl Joiner {
+    pub(crate) fn new(
+        left_keys: Vec<Symbol>,
+        right_keys: Vec<Symbol>,
+        span: SourceSpan,
+    ) -> Result<Self> {
+        ensure!(
+            left_keys.len() == right_keys.len(),
+            JoinKeysMismatchError(left_keys.len(), right_keys.len(), span)
+        );
+        Ok(Self {
+            left_keys,
+            right_keys,
+        })
+    }
+
     pub(crate) fn as_map(&self) -> BTreeMap<&str, &str> {
         self.left_keys
             .iter()
@@ -1212,7 +1816,7 @@ impl InnerJoin {
                     "mem_mat_join"
                 }
             }
-            RelAlgebra::Stored(_) => {
+            RelAlgebra::Stored(s) => {
                 let join_indices = self
                     .joiner
                     .join_indices(
@@ -1221,7 +1825,14 @@ impl InnerJoin {
                     )
                     .unwrap();
                 if join_is_prefix(&join_indices.1) {
-                    "stored_prefix_join"
+                    // binding only some leading key columns still allows a range scan,
+                    // but not a point lookup
+                    let n_bound = join_indices.1.len();
+                    if n_bound > 0 && n_bound < s.n_keys {
+                        "stored_partial_prefix_join"
+                    } else {
+                        "stored_prefix_join"
+                    }
                 } else {
                     "stored_mat_join"
                 }
@@ -1234,6 +1845,13 @@ impl InnerJoin {
             }
         }
     }
+
+    /// The side whose rows are streamed through the join. Plans are left-deep, so this is
+    /// always the left side, and each of its rows is matched against the right side, which
+    /// is either probed by key prefix or materialized beforehand.
+    pub(crate) fn driving_side(&self) -> &str {
+        "left"
+    }
 }
 
 fn join_is_prefix(right_join_indices: &[usize]) -> bool {
@@ -1245,3 +1863,556 @@ fn join_is_prefix(right_join_indices: &[usize]) -> bool {
     let l = indices.len();
     indices.into_iter().eq(0..l)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_rule::SimpleFixedRule;
+
+    #[test]
+    fn create_relation_rejects_zero_arity() {
+        let mut compiler = Compiler::new();
+        let err = compiler
+            .create_relation("empty".to_string(), 0, 0, SourceSpan::default())
+            .unwrap_err();
+        assert!(err.downcast_ref::<ZeroArityRelationError>().is_some());
+        assert!(compiler.get_relation("empty").is_err());
+    }
+
+    #[test]
+    fn create_relation_rejects_keys_exceeding_arity() {
+        let mut compiler = Compiler::new();
+        let err = compiler
+            .create_relation("rel".to_string(), 3, 2, SourceSpan::default())
+            .unwrap_err();
+        assert!(err.downcast_ref::<RelationKeysExceedArityError>().is_some());
+        assert!(compiler
+            .create_relation("rel".to_string(), 2, 2, SourceSpan::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn create_relation_counts_non_keys_in_arity() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        assert_eq!(compiler.get_relation("kv").unwrap().arity, 2);
+        assert!(compiler.compile_script("?[k, v] := *kv[k, v]").is_ok());
+    }
+
+    #[test]
+    fn create_relation_rejects_too_many_columns() {
+        let mut compiler = Compiler::new();
+        let cols = |n: usize| (0..n).map(|i| format!("c{i}: Int")).join(", ");
+        let err = compiler
+            .compile_script(&format!(":create wide {{{}}}", cols(256)))
+            .unwrap_err();
+        assert!(err.downcast_ref::<RelationTooWideError>().is_some());
+        assert!(compiler.get_relation("wide").is_err());
+
+        compiler
+            .compile_script(&format!(":create wide {{{}}}", cols(255)))
+            .unwrap();
+        assert_eq!(compiler.relation_arity("wide"), Some(255));
+    }
+
+    #[test]
+    fn relation_name_conflict_points_at_source() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let err = compiler
+            .compile_script(":create kv {k: Int => v: Int}")
+            .unwrap_err();
+        let conflict = err.downcast_ref::<CompiledRelNameConflictError>().unwrap();
+        assert_eq!(conflict.0, "kv");
+        assert_ne!(conflict.1, SourceSpan::default());
+    }
+
+    fn entry_aggr_kind(compiled: &[CompiledProgram]) -> AggrKind {
+        compiled
+            .iter()
+            .flat_map(|stratum| stratum.iter())
+            .find(|(k, _)| k.symbol().is_prog_entry())
+            .map(|(_, v)| v.aggr_kind())
+            .unwrap()
+    }
+
+    #[test]
+    fn group_concat_is_a_normal_aggregation() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create tags {k: Int, t: String}").unwrap();
+        let compiled = compiler
+            .compile_script("?[k, group_concat(t, ';')] := *tags[k, t]")
+            .unwrap();
+        assert_eq!(entry_aggr_kind(&compiled), AggrKind::Normal);
+    }
+
+    #[test]
+    fn count_unique_is_a_normal_aggregation() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create tags {k: Int, t: String}").unwrap();
+        let compiled = compiler
+            .compile_script("?[k, count_unique(t)] := *tags[k, t]")
+            .unwrap();
+        assert_eq!(entry_aggr_kind(&compiled), AggrKind::Normal);
+
+        let compiled = compiler
+            .compile_script("?[k, min(t)] := *tags[k, t]")
+            .unwrap();
+        assert_eq!(entry_aggr_kind(&compiled), AggrKind::Meet);
+    }
+
+    #[test]
+    fn strata_can_be_listed_by_name() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create edge {a: Int, b: Int}").unwrap();
+        let compiled = compiler
+            .compile_script(
+                r#"
+                reach[a, b] := *edge[a, b]
+                reach[a, b] := reach[a, c], *edge[c, b]
+                cnt[count(b)] := reach[1, b]
+                ?[n] := cnt[n]
+                "#,
+            )
+            .unwrap();
+        assert_eq!(Compiler::stratum_count(&compiled), 2);
+        // the aggregation in `cnt` puts it and the entry in a later stratum than `reach`
+        assert_eq!(Compiler::rule_names_in_stratum(&compiled, 0), vec!["reach"]);
+        assert_eq!(Compiler::rule_names_in_stratum(&compiled, 1), vec!["?", "cnt"]);
+        assert!(Compiler::rule_names_in_stratum(&compiled, 2).is_empty());
+    }
+
+    #[test]
+    fn output_bindings_of_a_join() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create a {x: Int, y: Int}").unwrap();
+        compiler.compile_script(":create b {y: Int, z: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[x, z] := *a[x, y], *b[y, z]")
+            .unwrap();
+        let mut relation = match compiled[0].values().next().unwrap() {
+            CompiledRuleSet::Rules(rules) => &rules[0].relation,
+            CompiledRuleSet::Fixed(_) => unreachable!(),
+        };
+        loop {
+            relation = match relation {
+                RelAlgebra::Reorder(r) => &r.relation,
+                RelAlgebra::Filter(f) => &f.parent,
+                RelAlgebra::Unification(u) => &u.parent,
+                _ => break,
+            }
+        }
+        assert!(matches!(relation, RelAlgebra::Join(_)));
+        assert_eq!(relation.output_bindings(), relation.bindings_after_eliminate());
+        // the join variable `y` is only needed inside the join
+        let output = relation.output_bindings();
+        assert_eq!(output.iter().map(|s| &**s).collect_vec(), vec!["x", "z"]);
+    }
+
+    #[test]
+    fn compiled_plan_as_json() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create a {x: Int, y: Int}").unwrap();
+        compiler.compile_script(":create b {y: Int, z: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[x, z] := *a[x, y], *b[y, z], x > 1")
+            .unwrap();
+        let plan = Compiler::compiled_to_json(&compiled).unwrap();
+        let reparsed: JsonValue = serde_json::from_str(&plan.to_string()).unwrap();
+        assert_eq!(reparsed, plan);
+
+        let entry = &plan[0][0];
+        assert_eq!(entry["name"], json!("?"));
+        assert_eq!(entry["arity"], json!(2));
+        let rule = &entry["rules"][0];
+        assert_eq!(rule["head"], json!(["x", "z"]));
+        let join = &rule["relation"];
+        assert!(join["op"].as_str().unwrap().ends_with("join"));
+        assert_eq!(join["bindings"], json!(["x", "z"]));
+        assert_eq!(join["joins_on"].as_object().unwrap().len(), 1);
+        assert_eq!(join["left"]["op"], json!("load_stored"));
+        assert_eq!(join["left"]["ref"], json!(":a"));
+        assert_eq!(join["left"]["filters"], json!(["gt(x, 1)"]));
+        assert_eq!(join["right"]["ref"], json!(":b"));
+    }
+
+    #[test]
+    fn joiner_rejects_mismatched_keys() {
+        let span = SourceSpan(3, 4);
+        let (a, b) = (Symbol::new("a", span), Symbol::new("b", span));
+        let err = Joiner::new(vec![a.clone(), b.clone()], vec![a.clone()], span).unwrap_err();
+        let mismatch = err.downcast_ref::<JoinKeysMismatchError>().unwrap();
+        assert_eq!((mismatch.0, mismatch.1, mismatch.2), (2, 1, span));
+
+        let joiner = Joiner::new(vec![a.clone()], vec![b.clone()], span).unwrap();
+        assert_eq!(joiner.as_map(), BTreeMap::from([("a", "b")]));
+
+        let err = RelAlgebra::unit(span)
+            .try_join(RelAlgebra::unit(span), vec![], vec![b], span)
+            .unwrap_err();
+        assert!(err.downcast_ref::<JoinKeysMismatchError>().is_some());
+    }
+
+    #[test]
+    fn clear_forgets_relations() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        compiler.compile_script(":create other {k: Int}").unwrap();
+
+        compiler.clear();
+        assert!(!compiler.relation_exists("kv"));
+        assert!(compiler.get_relation("kv").is_err());
+        assert!(compiler.compile_script("?[k, v] := *kv[k, v]").is_err());
+
+        compiler.compile_script(":create kv {k: Int, l: Int => v: Int}").unwrap();
+        assert_eq!(compiler.relation_arity("kv"), Some(3));
+        assert_eq!(compiler.relation_arity("other"), None);
+    }
+
+    #[test]
+    fn clear_restores_new_state() {
+        let mut compiler = Compiler::new();
+        compiler.register_fixed_rule(
+            "Custom".to_string(),
+            Arc::new(Box::new(SimpleFixedRule::new(1, |inputs, _| {
+                Ok(inputs.into_iter().next().unwrap())
+            }))),
+        );
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+
+        compiler.clear();
+        assert!(!compiler.relation_exists("kv"));
+        assert!(compiler
+            .compile_script("r[a] := a = 1\n?[a] <~ Custom(r[b])")
+            .is_err());
+        assert!(compiler
+            .compile_script("r[a] := a = 1\n?[a] <~ Limit(r[b], limit: 1)")
+            .is_ok());
+    }
+
+    #[test]
+    fn replace_creates_missing_relation() {
+        let mut compiler = Compiler::new();
+        compiler
+            .compile_script("?[k, v] <- [[1, 2]] :replace kv {k: Int => v: Int}")
+            .unwrap();
+        assert_eq!(compiler.relation_arity("kv"), Some(2));
+        assert!(compiler.compile_script("?[k, v] := *kv[k, v]").is_ok());
+    }
+
+    #[test]
+    fn replace_overwrites_existing_schema() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        compiler
+            .compile_script("?[k, l, v, w] <- [] :replace kv {k: Int, l: Int => v: Int, w: Int}")
+            .unwrap();
+        let handle = compiler.get_relation("kv").unwrap();
+        assert_eq!((handle.arity, handle.n_keys), (4, 2));
+        assert!(compiler.compile_script("?[k, v] := *kv[k, l, v, w]").is_ok());
+        let err = compiler.compile_script("?[k, v] := *kv[k, v]").unwrap_err();
+        assert!(err.downcast_ref::<ArityMismatch>().is_some());
+
+        // replacing again with the same schema is fine too
+        compiler
+            .compile_script("?[k, l, v, w] <- [] :replace kv {k: Int, l: Int => v: Int, w: Int}")
+            .unwrap();
+        assert_eq!(compiler.relation_arity("kv"), Some(4));
+    }
+
+    #[test]
+    fn relation_arity_lookup() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int, l: Int => v: Int}").unwrap();
+        assert_eq!(compiler.relation_arity("kv"), Some(3));
+        assert_eq!(compiler.relation_arity("missing"), None);
+    }
+
+    #[test]
+    fn compile_script_resolves_params() {
+        let mut compiler = Compiler::new();
+        let params = BTreeMap::from([("v".to_string(), DataValue::from(42))]);
+        let compiled = compiler
+            .compile_script_with_params("?[x] := x = $v", params)
+            .unwrap();
+        let entry = compiled[0]
+            .iter()
+            .find(|(k, _)| k.symbol().is_prog_entry())
+            .map(|(_, v)| v)
+            .unwrap();
+        let CompiledRuleSet::Rules(rules) = entry else {
+            panic!("expected rules for the entry, got {entry:?}")
+        };
+        match &rules[0].relation {
+            RelAlgebra::Unification(UnificationRA {
+                expr: Expr::Const { val, .. },
+                ..
+            }) => assert_eq!(*val, DataValue::from(42)),
+            r => panic!("expected the parameter to be inlined as a constant, got {r:?}"),
+        }
+
+        assert!(compiler.compile_script("?[x] := x = $v").is_err());
+    }
+
+    #[test]
+    fn unreferenced_rule_is_reported_unused() {
+        let mut compiler = Compiler::new();
+        let (compiled, unused) = compiler
+            .compile_script_reporting_unused(
+                r#"
+                helper[a] := a = 1
+                unused[b] := b = 2
+                ?[a] := helper[a]
+                "#,
+                &BTreeMap::new(),
+            )
+            .unwrap();
+        assert!(!compiled.is_empty());
+        assert_eq!(unused, vec!["unused".to_string()]);
+
+        let (_, unused) = compiler
+            .compile_script_reporting_unused(
+                "helper[a] := a = 1\n?[a] := helper[a]",
+                &BTreeMap::new(),
+            )
+            .unwrap();
+        assert!(unused.is_empty());
+
+        let params = BTreeMap::from([("v".to_string(), DataValue::from(1))]);
+        let (_, unused) = compiler
+            .compile_script_reporting_unused(
+                "helper[a] := a = $v\nunused[b] := b = $v\n?[a] := helper[a]",
+                &params,
+            )
+            .unwrap();
+        assert_eq!(unused, vec!["unused".to_string()]);
+    }
+
+    #[test]
+    fn registered_fixed_rule_is_visible_to_parser() {
+        use crate::fixed_rule::SimpleFixedRule;
+        use crate::runtime::db::NamedRows;
+
+        let script = "?[a, b] <~ Pairs()";
+        let mut compiler = Compiler::new();
+        assert!(compiler.compile_script(script).is_err());
+
+        compiler.register_fixed_rule(
+            "Pairs".to_string(),
+            Arc::new(Box::new(SimpleFixedRule::new(2, |_, _| Ok(NamedRows::default())))),
+        );
+        let compiled = compiler.compile_script(script).unwrap();
+        let entry = compiled
+            .iter()
+            .flat_map(|prog| prog.values())
+            .find(|rule_set| matches!(rule_set, CompiledRuleSet::Fixed(_)))
+            .expect("the entry should be compiled to the fixed rule");
+        assert_eq!(entry.arity(), 2);
+    }
+
+    #[test]
+    fn relation_lookup_case_sensitivity() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create Users {id: Int}").unwrap();
+
+        assert_eq!(compiler.get_relation("Users").unwrap().name, "Users");
+        assert!(compiler.get_relation("users").is_err());
+        assert_eq!(compiler.get_relation_ci("users").unwrap().name, "Users");
+        assert_eq!(compiler.get_relation_ci("USERS").unwrap().name, "Users");
+        assert!(compiler
+            .get_relation_ci("accounts")
+            .unwrap_err()
+            .downcast_ref::<StoredRelationNotFoundError>()
+            .is_some());
+
+        compiler.compile_script(":create users {id: Int}").unwrap();
+        assert_eq!(compiler.get_relation("users").unwrap().name, "users");
+        let err = compiler.get_relation_ci("USERS").unwrap_err();
+        assert!(err.downcast_ref::<AmbiguousRelationNameError>().is_some());
+        assert_eq!(compiler.get_relation_ci("users").unwrap().name, "users");
+        assert_eq!(compiler.get_relation_ci("Users").unwrap().name, "Users");
+    }
+
+    #[test]
+    fn relation_name_ci_resolves_created_name() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create Users {id: Int}").unwrap();
+        assert_eq!(compiler.relation_name_ci("users").unwrap(), "Users");
+        assert_eq!(compiler.relation_name_ci("Users").unwrap(), "Users");
+        assert!(compiler.relation_name_ci("accounts").is_err());
+
+        compiler.compile_script(":create USERS {id: Int}").unwrap();
+        assert_eq!(compiler.relation_name_ci("USERS").unwrap(), "USERS");
+        assert!(compiler
+            .relation_name_ci("users")
+            .unwrap_err()
+            .downcast_ref::<AmbiguousRelationNameError>()
+            .is_some());
+    }
+
+    #[test]
+    fn eval_const_expression_folds_constants_and_params() {
+        let compiler = Compiler::new();
+        let no_params = BTreeMap::new();
+        assert_eq!(
+            compiler.eval_const_expression("1 + 2 * 3", &no_params).unwrap(),
+            DataValue::from(7)
+        );
+
+        let params = BTreeMap::from([("n".to_string(), DataValue::from(4))]);
+        assert_eq!(
+            compiler.eval_const_expression("$n * 10 + 1", &params).unwrap(),
+            DataValue::from(41)
+        );
+        assert!(compiler.eval_const_expression("$n + 1", &no_params).is_err());
+        assert!(compiler.eval_const_expression("x + 1", &no_params).is_err());
+    }
+
+    #[test]
+    fn put_fills_omitted_columns_with_defaults() {
+        let mut compiler = Compiler::new();
+        compiler
+            .compile_script(":create kv {k: Int => v: Int default 7, note: String?}")
+            .unwrap();
+        // arity of the entry rule and the constants its unifications bind
+        let entry = |compiled: &[CompiledProgram]| {
+            let rule_set = compiled
+                .iter()
+                .flat_map(|prog| prog.iter())
+                .find(|(sym, _)| sym.symbol().is_prog_entry())
+                .map(|(_, rule_set)| rule_set)
+                .unwrap();
+            let CompiledRuleSet::Rules(rules) = rule_set else {
+                panic!("expected rules for the entry, got {rule_set:?}")
+            };
+            let mut consts = vec![];
+            let mut rel = &rules[0].relation;
+            while let RelAlgebra::Unification(u) = rel {
+                if let Expr::Const { val, .. } = &u.expr {
+                    consts.push(val.clone());
+                }
+                rel = &u.parent;
+            }
+            (rule_set.arity(), consts)
+        };
+
+        let compiled = compiler
+            .compile_script("?[k, v, note] := k = 1, v = 2, note = 'x' :put kv {k => v, note}")
+            .unwrap();
+        let (arity, consts) = entry(&compiled);
+        assert_eq!(arity, 3);
+        assert!(!consts.contains(&DataValue::from(7)));
+
+        let compiled = compiler
+            .compile_script("?[k] := k = 1 :put kv {k}")
+            .unwrap();
+        let (arity, consts) = entry(&compiled);
+        assert_eq!(arity, 3);
+        assert!(consts.contains(&DataValue::from(7)));
+        assert!(consts.contains(&DataValue::Null));
+
+        compiler
+            .compile_script(":create strict {k: Int => v: Int}")
+            .unwrap();
+        let err = compiler
+            .compile_script("?[k] <- [[1]] :put strict {k}")
+            .unwrap_err();
+        assert!(err.downcast_ref::<ColumnNotProvidedError>().is_some());
+    }
+
+    #[test]
+    fn head_names_follow_reordered_output() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[v, total, k] := *kv[k, v], total = k + v")
+            .unwrap();
+        let rule_set = compiled
+            .iter()
+            .flat_map(|prog| prog.iter())
+            .find(|(sym, _)| sym.symbol().is_prog_entry())
+            .map(|(_, rule_set)| rule_set)
+            .unwrap();
+        let CompiledRuleSet::Rules(rules) = rule_set else {
+            panic!("expected rules for the entry, got {rule_set:?}")
+        };
+
+        assert_eq!(rules[0].head_names(), vec!["v", "total", "k"]);
+        assert!(matches!(rules[0].relation, RelAlgebra::Reorder(_)));
+        let produced = rules[0].relation.bindings_after_eliminate();
+        assert_eq!(
+            produced.iter().map(|symb| symb.name.as_str()).collect_vec(),
+            rules[0].head_names()
+        );
+    }
+
+    #[test]
+    fn validate_script_reports_diagnostics_without_compiling() {
+        let compiler = Compiler::new();
+        let no_params = BTreeMap::new();
+        assert!(compiler.validate_script("?[a] := a = 1", &no_params).is_ok());
+        let err = compiler
+            .validate_script("?[a, b] := a = 1", &no_params)
+            .unwrap_err();
+        assert!(err.downcast_ref::<UnboundSymbolInRuleHead>().is_some());
+        assert!(compiler
+            .validate_script("r[a] := a = 1\n?[a, b] := r[a], not r[b]", &no_params)
+            .is_err());
+        assert!(compiler.validate_script("?[a] := a = ", &no_params).is_err());
+    }
+
+    #[test]
+    fn validate_script_takes_params() {
+        let compiler = Compiler::new();
+        let params = BTreeMap::from([("v".to_string(), DataValue::from(1))]);
+        assert!(compiler.validate_script("?[a] := a = $v", &params).is_ok());
+        assert!(compiler
+            .validate_script("?[a] := a = $v", &BTreeMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn inline_fixed_relation_exposes_rows() {
+        let fixed = InlineFixedRA {
+            bindings: vec![
+                Symbol::new("a", SourceSpan::default()),
+                Symbol::new("b", SourceSpan::default()),
+            ],
+            data: vec![
+                vec![DataValue::from(1), DataValue::from("x")],
+                vec![DataValue::from(2), DataValue::from("y")],
+            ],
+            to_eliminate: Default::default(),
+            span: SourceSpan::default(),
+        };
+        assert_eq!(
+            fixed.bindings().iter().map(|symb| symb.name.as_str()).collect_vec(),
+            vec!["a", "b"]
+        );
+        assert_eq!(fixed.rows().len(), 2);
+        assert_eq!(fixed.rows()[1], vec![DataValue::from(2), DataValue::from("y")]);
+
+        let unit = InlineFixedRA::unit(SourceSpan::default());
+        assert!(unit.bindings().is_empty());
+        assert_eq!(unit.rows(), &[Vec::<DataValue>::new()]);
+    }
+
+    #[test]
+    fn arity_mismatch_names_rule_or_relation() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let help_of = |err: &Error| err.help().map(|h| h.to_string()).unwrap_or_default();
+
+        let err = compiler
+            .compile_script("kv[a] := a = 1\n?[a] := kv[a, b]")
+            .unwrap_err();
+        let mismatch = err.downcast_ref::<ArityMismatch>().unwrap();
+        assert_eq!(mismatch.0, "rule");
+        assert!(help_of(&err).contains("rule kv"));
+
+        let err = compiler.compile_script("?[a] := *kv[a]").unwrap_err();
+        let mismatch = err.downcast_ref::<ArityMismatch>().unwrap();
+        assert_eq!(mismatch.0, "relation");
+        assert!(help_of(&err).contains("relation kv"));
+    }
+}
@@ -771,15 +771,48 @@ impl Debug for Op {
 pub(crate) fn get_op(name: &str) -> Option<&'static Op> {
     Some(match name {
         "list" => &OP_LIST,
+        "dedup_collect" => &OP_DEDUP_COLLECT,
+        "flatten" => &OP_FLATTEN,
+        "assoc_get" => &OP_ASSOC_GET,
+        "slice_step" => &OP_SLICE_STEP,
+        "list_set" => &OP_LIST_SET,
+        "list_insert" => &OP_LIST_INSERT,
+        "list_remove" => &OP_LIST_REMOVE,
+        "field" => &OP_FIELD,
+        "fill" => &OP_FILL,
+        "prefix_sum" => &OP_PREFIX_SUM,
+        "diff" => &OP_DIFF,
+        "clamp_list" => &OP_CLAMP_LIST,
+        "cosine_similarity" => &OP_COSINE_SIMILARITY,
+        "replace_null" => &OP_REPLACE_NULL,
+        "transpose" => &OP_TRANSPOSE,
+        "uniq_count" => &OP_UNIQ_COUNT,
+        "hash" => &OP_HASH,
+        "sha256" => &OP_SHA256,
+        "crc32" => &OP_CRC32,
         "add" => &OP_ADD,
         "sub" => &OP_SUB,
         "mul" => &OP_MUL,
         "div" => &OP_DIV,
         "minus" => &OP_MINUS,
         "mod" => &OP_MOD, "max" => &OP_MAX,
+        "safe_mod" => &OP_SAFE_MOD,
+        "gcd" => &OP_GCD,
+        "lcm" => &OP_LCM,
+        "factorial" => &OP_FACTORIAL,
+        "binomial" => &OP_BINOMIAL,
         "min" => &OP_MIN,
+        "greatest" => &OP_GREATEST,
+        "least" => &OP_LEAST,
+        "max_by" => &OP_MAX_BY,
+        "min_by" => &OP_MIN_BY,
+        "median" => &OP_MEDIAN,
+        "variance" => &OP_VARIANCE,
+        "stddev" => &OP_STDDEV,
         "sqrt" => &OP_SQRT,
         "eq" => &OP_EQ,
+        "nullif" => &OP_NULLIF,
+        "deep_equal" => &OP_DEEP_EQUAL,
         "neq" => &OP_NEQ,
         "gt" => &OP_GT,
         "ge" => &OP_GE,
@@ -790,12 +823,62 @@ pub(crate) fn get_op(name: &str) -> Option<&'static Op> {
         "negate" => &OP_NEGATE,
         "is_in" => &OP_IS_IN,
         "is_uuid" => &OP_IS_UUID,
+        "is_null" => &OP_IS_NULL,
+        "is_num" => &OP_IS_NUM,
+        "is_int" => &OP_IS_INT,
+        "is_float" => &OP_IS_FLOAT,
+        "is_string" => &OP_IS_STRING,
+        "is_list" => &OP_IS_LIST,
+        "is_bytes" => &OP_IS_BYTES,
+        "is_bool" => &OP_IS_BOOL,
+        "is_json" => &OP_IS_JSON,
+        "is_finite" => &OP_IS_FINITE,
+        "is_infinite" => &OP_IS_INFINITE,
+        "is_nan" => &OP_IS_NAN,
+        "nan_to_null" => &OP_NAN_TO_NULL,
         "to_string" => &OP_TO_STRING,
+        "list_to_json" => &OP_LIST_TO_JSON,
+        "json_to_list" => &OP_JSON_TO_LIST,
+        "json_path_get" => &OP_JSON_PATH_GET,
+        "json_keys" => &OP_JSON_KEYS,
+        "json_values" => &OP_JSON_VALUES,
+        "json_set" => &OP_JSON_SET,
+        "json_remove" => &OP_JSON_REMOVE,
+        "json_deep_copy" => &OP_JSON_DEEP_COPY,
+        "pretty_json" => &OP_PRETTY_JSON,
+        "to_float" => &OP_TO_FLOAT,
+        "parse_float_locale" => &OP_PARSE_FLOAT_LOCALE,
+        "to_int" => &OP_TO_INT,
+        "number_format" => &OP_NUMBER_FORMAT,
+        "zero_fill" => &OP_ZERO_FILL,
+        "chars" => &OP_CHARS,
+        "byte_length" => &OP_BYTE_LENGTH,
+        "pad_center" => &OP_PAD_CENTER,
+        "count_substr" => &OP_COUNT_SUBSTR,
+        "positions" => &OP_POSITIONS,
+        "levenshtein" => &OP_LEVENSHTEIN,
+        "trigram_similarity" => &OP_TRIGRAM_SIMILARITY,
+        "wrap" => &OP_WRAP,
+        "lines" => &OP_LINES,
+        "unlines" => &OP_UNLINES,
+        "str_reverse_graphemes" => &OP_STR_REVERSE_GRAPHEMES,
+        "unicode_normalize" => &OP_UNICODE_NORMALIZE,
+        "casefold" => &OP_CASEFOLD,
+        "regex_matches" => &OP_REGEX_MATCHES,
+        "regex_extract" => &OP_REGEX_EXTRACT,
+        "regex_replace" => &OP_REGEX_REPLACE,
         "int_range" => &OP_INT_RANGE,
         "to_uuid" => &OP_TO_UUID,
         "rand_uuid_v4" => &OP_RAND_UUID_V4,
+        "rand_float" => &OP_RAND_FLOAT,
+        "rand_int" => &OP_RAND_INT,
+        "sample" => &OP_SAMPLE,
+        "shuffle" => &OP_SHUFFLE,
         "uuid_timestamp" => &OP_UUID_TIMESTAMP,
         "now" => &OP_NOW,
+        "now_micros" => &OP_NOW_MICROS,
+        "validity" => &OP_VALIDITY,
+        "validity_to_millis" => &OP_VALIDITY_TO_MILLIS,
         "format_timestamp" => &OP_FORMAT_TIMESTAMP,
         "parse_timestamp" => &OP_PARSE_TIMESTAMP,
         _ => return None,
@@ -20,7 +20,7 @@ use super::program::{
     NormalFormAtom, NormalFormInlineRule, NormalFormProgram, NormalFormRulesOrFixed,
     StratifiedMagicProgram, StratifiedNormalFormProgram,
 };
-use crate::compile::{ColType, NullableColType};
+use crate::data::relation::{ColType, NullableColType};
 use crate::compile::symb::{Symbol, PROG_ENTRY};
 use crate::parse::SourceSpan;
 use crate::query::logical::NamedFieldNotFound;
@@ -8,7 +8,6 @@ pub mod symb;
 pub mod expr;
 
 pub use compile::Compiler;
-pub use compile::{ColType, NullableColType};
 pub use compile::IndexPositionUse;
 pub use compile::{
     CompiledProgram,
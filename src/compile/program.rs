@@ -736,6 +736,45 @@ pub(crate) struct NormalFormProgram {
     pub(crate) disable_magic_rewrite: bool,
 }
 
+#[derive(Debug, Error, Diagnostic)]
+#[error("Symbol '{0}' in rule head is unbound")]
+#[diagnostic(code(eval::unbound_symb_in_head))]
+#[diagnostic(help(
+    "Note that symbols occurring only in negated positions are not considered bound"
+))]
+pub(crate) struct UnboundSymbolInRuleHead(pub(crate) String, #[label] pub(crate) SourceSpan);
+
+impl NormalFormProgram {
+    /// Fail on the first rule head symbol that no positive atom or unification binds,
+    /// which would otherwise only be found when compiling the rule body.
+    pub(crate) fn ensure_heads_bound(&self) -> Result<()> {
+        for rules_or_fixed in self.prog.values() {
+            let NormalFormRulesOrFixed::Rules { rules } = rules_or_fixed else {
+                continue;
+            };
+            for rule in rules {
+                let mut bound = BTreeSet::new();
+                for atom in &rule.body {
+                    match atom {
+                        NormalFormAtom::Rule(r) => bound.extend(r.args.iter()),
+                        NormalFormAtom::Relation(r) => bound.extend(r.args.iter()),
+                        NormalFormAtom::Unification(u) => {
+                            bound.insert(&u.binding);
+                        }
+                        NormalFormAtom::NegatedRule(_)
+                        | NormalFormAtom::NegatedRelation(_)
+                        | NormalFormAtom::Predicate(_) => {}
+                    }
+                }
+                if let Some(unbound) = rule.head.iter().find(|symb| !bound.contains(symb)) {
+                    bail!(UnboundSymbolInRuleHead(unbound.to_string(), unbound.span))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct StratifiedMagicProgram(pub(crate) Vec<MagicProgram>);
 
@@ -771,23 +810,38 @@ pub(crate) struct MagicProgram {
     pub(crate) prog: BTreeMap<MagicSymbol, MagicRulesOrFixed>,
 }
 
+/// The name of a rule set in a compiled program: a rule of the script, or one of the
+/// rules introduced by the magic sets rewrite. Displays as in `explain`.
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub(crate) enum MagicSymbol {
+pub enum MagicSymbol {
+    /// A rule of the script, not rewritten
     Muggle {
+        /// The rule name
         inner: Symbol,
     },
+    /// The magic-rewritten version of a rule for the given adornment
     Magic {
+        /// The rule name
         inner: Symbol,
+        /// Which arguments are bound
         adornment: Vec<bool>,
     },
+    /// The rule feeding bound arguments into a magic-rewritten rule
     Input {
+        /// The rule name
         inner: Symbol,
+        /// Which arguments are bound
         adornment: Vec<bool>,
     },
+    /// A supplementary rule holding a prefix of the body of a magic-rewritten rule
     Sup {
+        /// The rule name
         inner: Symbol,
+        /// Which arguments are bound
         adornment: Vec<bool>,
+        /// The index of the rule within its rule set
         rule_idx: u16,
+        /// The index of the body prefix
         sup_idx: u16,
     },
 }
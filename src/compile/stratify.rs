@@ -221,7 +221,62 @@ fn make_scc_reduced_graph(
     (indices, ret)
 }
 
+/// A read-only description of how a program is split into strata,
+/// taken before the magic sets rewrite.
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Serialize)]
+pub struct StratificationInfo {
+    /// The rule names in each stratum, in evaluation order
+    pub strata: Vec<Vec<String>>,
+    /// Dependencies between the rules that take part in the program
+    pub dependencies: Vec<RuleDependency>,
+}
+
+/// An edge of the rule dependency graph
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Serialize)]
+pub struct RuleDependency {
+    /// The rule whose body refers to `depends_on`
+    pub rule: String,
+    /// The rule being referred to
+    pub depends_on: String,
+    /// Whether `depends_on` must be fully evaluated first, i.e. it is used through
+    /// negation, a non-meet aggregation or a fixed rule
+    pub forces_new_stratum: bool,
+}
+
+impl StratifiedNormalFormProgram {
+    pub(crate) fn describe(&self, dependencies: Vec<RuleDependency>) -> StratificationInfo {
+        let strata = self
+            .0
+            .iter()
+            .rev()
+            .map(|stratum| stratum.prog.keys().map(|k| k.to_string()).collect_vec())
+            .collect_vec();
+        let included: BTreeSet<&str> = strata.iter().flatten().map(|s| s as &str).collect();
+        let dependencies = dependencies
+            .into_iter()
+            .filter(|d| included.contains(&d.rule as &str))
+            .collect();
+        StratificationInfo {
+            strata,
+            dependencies,
+        }
+    }
+}
+
 impl NormalFormProgram {
+    pub(crate) fn dependencies(&self) -> Vec<RuleDependency> {
+        convert_normal_form_program_to_graph(self)
+            .into_iter()
+            .flat_map(|(k, tos)| {
+                tos.into_iter().map(move |(to, forces_new_stratum)| RuleDependency {
+                    rule: k.to_string(),
+                    depends_on: to.to_string(),
+                    forces_new_stratum,
+                })
+            })
+            .collect()
+    }
+
     /// returns the stratified program and the store lifetimes of the intermediate relations
     pub fn into_stratified_program(
         self,
@@ -311,4 +366,57 @@ impl NormalFormProgram {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::compile::Compiler;
+    use crate::data::value::DataValue;
+
+    #[test]
+    fn recursive_program_strata() {
+        let compiler = Compiler::new();
+        let info = compiler
+            .stratify_script(
+                r#"
+                r[a] := a = 1
+                r[a] := r[b], a = b + 1, a < 10
+                cnt[count(a)] := r[a]
+                unused[a] := a = 0
+                ?[c] := cnt[c]
+                "#,
+                &BTreeMap::new(),
+            )
+            .unwrap();
+        assert_eq!(info.strata.len(), 2);
+        assert_eq!(info.strata[0], vec!["r".to_string()]);
+        assert!(info.strata[1].contains(&"?".to_string()));
+        assert!(info.strata[1].contains(&"cnt".to_string()));
+        assert!(!info.strata.iter().flatten().any(|r| r == "unused"));
+
+        let recursion = info
+            .dependencies
+            .iter()
+            .find(|d| d.rule == "r" && d.depends_on == "r")
+            .unwrap();
+        assert!(!recursion.forces_new_stratum);
+        let aggregation = info
+            .dependencies
+            .iter()
+            .find(|d| d.rule == "cnt" && d.depends_on == "r")
+            .unwrap();
+        assert!(aggregation.forces_new_stratum);
+    }
+
+    #[test]
+    fn parameterised_script_strata() {
+        let compiler = Compiler::new();
+        let params = BTreeMap::from([("limit".to_string(), DataValue::from(10))]);
+        let script = r#"
+            r[a] := a = 1
+            r[a] := r[b], a = b + 1, a < $limit
+            ?[a] := r[a]
+            "#;
+        let info = compiler.stratify_script(script, &params).unwrap();
+        assert_eq!(info.strata.len(), 1);
+        assert!(compiler.stratify_script(script, &BTreeMap::new()).is_err());
+    }
 }
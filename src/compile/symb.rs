@@ -91,6 +91,9 @@ impl Symbol {
     pub(crate) fn is_generated_ignored_symbol(&self) -> bool {
         self.name.starts_with('~')
     }
+    pub(crate) fn is_generated_symbol(&self) -> bool {
+        self.name.starts_with('*')
+    }
     pub(crate) fn ensure_valid_field(&self) -> Result<()> {
         if self.name.contains('(') || self.name.contains(')') {
             #[derive(Debug, Error, Diagnostic)]
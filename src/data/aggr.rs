@@ -385,6 +385,48 @@ impl NormalAggrObj for AggrCollect {
     }
 }
 
+define_aggr!(AGGR_GROUP_CONCAT, false);
+
+pub(crate) struct AggrGroupConcat {
+    separator: String,
+    accum: Option<String>,
+}
+
+impl Default for AggrGroupConcat {
+    fn default() -> Self {
+        Self::new(",".to_string())
+    }
+}
+
+impl AggrGroupConcat {
+    fn new(separator: String) -> Self {
+        Self {
+            separator,
+            accum: None,
+        }
+    }
+}
+
+impl NormalAggrObj for AggrGroupConcat {
+    fn set(&mut self, value: &DataValue) -> Result<()> {
+        let s = value
+            .get_str()
+            .ok_or_else(|| miette!("cannot compute 'group_concat' for value {:?}", value))?;
+        match &mut self.accum {
+            None => self.accum = Some(s.to_string()),
+            Some(accum) => {
+                accum.push_str(&self.separator);
+                accum.push_str(s);
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> Result<DataValue> {
+        Ok(DataValue::from(self.accum.clone().unwrap_or_default()))
+    }
+}
+
 define_aggr!(AGGR_CHOICE_RAND, false);
 
 // // pub(crate) struct AggrChoiceRand {
@@ -1174,6 +1216,7 @@ pub(crate) fn parse_aggr(name: &str) -> Option<&'static Aggregation> {
         "mean" => &AGGR_MEAN,
         "choice" => &AGGR_CHOICE,
         "collect" => &AGGR_COLLECT,
+        "group_concat" => &AGGR_GROUP_CONCAT,
         "shortest" => &AGGR_SHORTEST,
         "min_cost" => &AGGR_MIN_COST,
         "bit_and" => &AGGR_BIT_AND,
@@ -1249,6 +1292,19 @@ impl Aggregation {
                     AggrCollect::new(arg as usize)
                 }
             }),
+            name if name == AGGR_GROUP_CONCAT.name => Box::new({
+                if args.is_empty() {
+                    AggrGroupConcat::default()
+                } else {
+                    let sep = args[0].get_str().ok_or_else(|| {
+                        miette!(
+                            "the argument to 'group_concat' must be a string, got {:?}",
+                            args[0]
+                        )
+                    })?;
+                    AggrGroupConcat::new(sep.to_string())
+                }
+            }),
             _ => unreachable!(),
         });
         Ok(())
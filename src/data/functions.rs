@@ -6,11 +6,13 @@
  * You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::cmp::Reverse;
-use std::collections::BTreeSet;
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet};
 use std::mem;
 use std::ops::{Div, Rem};
 use std::str::FromStr;
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use base64::engine::general_purpose::STANDARD;
@@ -19,12 +21,16 @@ use chrono::{DateTime, TimeZone, Utc};
 use itertools::Itertools;
 #[cfg(target_arch = "wasm32")]
 use js_sys::Date;
+use lazy_static::lazy_static;
 use miette::{bail, ensure, miette, IntoDiagnostic, Result};
 // use num_traits::FloatConst;
-// use rand::prelude::*;
+use rand::prelude::*;
+use regex::Regex;
 use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 // use smartstring::SmartString;
 use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::v1::Timestamp;
 
 use crate::compile::expr::Op;
@@ -55,6 +61,8 @@ macro_rules! simple_define_op {
     };
 }
 
+/// Ordering comparisons are only allowed between values of the same type;
+/// the result then follows the `Ord` implementation of `DataValue`.
 fn ensure_same_value_type(a: &DataValue, b: &DataValue) -> Result<()> {
     use DataValue::*;
     if !matches!(
@@ -67,6 +75,9 @@ fn ensure_same_value_type(a: &DataValue, b: &DataValue) -> Result<()> {
             // | (Regex(_), Regex(_))
             | (List(_), List(_))
             | (Set(_), Set(_))
+            | (Uuid(_), Uuid(_))
+            | (Validity(_), Validity(_))
+            | (Json(_), Json(_))
             | (Bot, Bot)
     ) {
         bail!(
@@ -83,7 +94,13 @@ pub(crate) fn op_list(args: &[DataValue]) -> Result<DataValue> {
     Ok(DataValue::List(args.to_vec()))
 }
 
-fn to_json(d: &DataValue) -> JsonValue {
+define_op!(OP_DEDUP_COLLECT, 0, true);
+/// A set of the arguments, so that equal arguments appear only once.
+pub(crate) fn op_dedup_collect(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::Set(args.iter().cloned().collect()))
+}
+
+pub(crate) fn to_json(d: &DataValue) -> JsonValue {
     match d {
         DataValue::Null => {
             json!(null)
@@ -144,11 +161,144 @@ pub(crate) fn op_eq(args: &[DataValue]) -> Result<DataValue> {
     }))
 }
 
+define_op!(OP_NULLIF, 2, false);
+/// Null if both arguments are equal by the rules of `eq`, otherwise the first argument.
+pub(crate) fn op_nullif(args: &[DataValue]) -> Result<DataValue> {
+    Ok(if op_eq(args)? == DataValue::from(true) {
+        DataValue::Null
+    } else {
+        args[0].clone()
+    })
+}
+
+define_op!(OP_DEEP_EQUAL, 2, false);
+/// Like `eq`, but ints and floats compare numerically at every level of nesting.
+pub(crate) fn op_deep_equal(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(deep_equal(&args[0], &args[1])))
+}
+
+fn deep_equal(a: &DataValue, b: &DataValue) -> bool {
+    match (a, b) {
+        (DataValue::Num(Num::Float(f)), DataValue::Num(Num::Int(i)))
+        | (DataValue::Num(Num::Int(i)), DataValue::Num(Num::Float(f))) => *i as f64 == *f,
+        (DataValue::List(l), DataValue::List(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(a, b)| deep_equal(a, b))
+        }
+        (DataValue::Set(l), DataValue::Set(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(a, b)| deep_equal(a, b))
+        }
+        (DataValue::Json(JsonData(l)), DataValue::Json(JsonData(r))) => deep_equal_json(l, r),
+        (a, b) => a == b,
+    }
+}
+
+fn deep_equal_json(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(l), JsonValue::Number(r)) => match (l.as_i64(), r.as_i64()) {
+            (Some(l), Some(r)) => l == r,
+            _ => l.as_f64() == r.as_f64(),
+        },
+        (JsonValue::Array(l), JsonValue::Array(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(a, b)| deep_equal_json(a, b))
+        }
+        (JsonValue::Object(l), JsonValue::Object(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(k, v)| r.get(k).is_some_and(|rv| deep_equal_json(v, rv)))
+        }
+        (a, b) => a == b,
+    }
+}
+
 define_op!(OP_IS_UUID, 1, false);
 pub(crate) fn op_is_uuid(args: &[DataValue]) -> Result<DataValue> {
     Ok(DataValue::from(matches!(args[0], DataValue::Uuid(_))))
 }
 
+define_op!(OP_IS_NULL, 1, false);
+pub(crate) fn op_is_null(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Null)))
+}
+
+define_op!(OP_IS_NUM, 1, false);
+pub(crate) fn op_is_num(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Num(_))))
+}
+
+define_op!(OP_IS_INT, 1, false);
+pub(crate) fn op_is_int(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Num(Num::Int(_)))))
+}
+
+define_op!(OP_IS_FLOAT, 1, false);
+pub(crate) fn op_is_float(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Num(Num::Float(_)))))
+}
+
+define_op!(OP_IS_STRING, 1, false);
+pub(crate) fn op_is_string(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Str(_))))
+}
+
+define_op!(OP_IS_LIST, 1, false);
+pub(crate) fn op_is_list(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::List(_))))
+}
+
+define_op!(OP_IS_BYTES, 1, false);
+pub(crate) fn op_is_bytes(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Bytes(_))))
+}
+
+define_op!(OP_IS_BOOL, 1, false);
+pub(crate) fn op_is_bool(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Bool(_))))
+}
+
+define_op!(OP_IS_JSON, 1, false);
+pub(crate) fn op_is_json(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(matches!(args[0], DataValue::Json(_))))
+}
+
+define_op!(OP_IS_FINITE, 1, false);
+pub(crate) fn op_is_finite(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(match &args[0] {
+        DataValue::Num(Num::Int(_)) => true,
+        DataValue::Num(Num::Float(f)) => f.is_finite(),
+        _ => bail!("'is_finite' requires numbers"),
+    }))
+}
+
+define_op!(OP_IS_INFINITE, 1, false);
+pub(crate) fn op_is_infinite(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(match &args[0] {
+        DataValue::Num(Num::Int(_)) => false,
+        DataValue::Num(Num::Float(f)) => f.is_infinite(),
+        _ => bail!("'is_infinite' requires numbers"),
+    }))
+}
+
+define_op!(OP_IS_NAN, 1, false);
+pub(crate) fn op_is_nan(args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(match &args[0] {
+        DataValue::Num(Num::Int(_)) => false,
+        DataValue::Num(Num::Float(f)) => f.is_nan(),
+        _ => bail!("'is_nan' requires numbers"),
+    }))
+}
+
+define_op!(OP_NAN_TO_NULL, 1, true);
+/// Replaces NaN and infinite floats with the optional second argument, or null.
+pub(crate) fn op_nan_to_null(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(args.len() <= 2, "'nan_to_null' takes at most two arguments");
+    Ok(match &args[0] {
+        DataValue::Num(Num::Float(f)) if !f.is_finite() => {
+            args.get(1).cloned().unwrap_or(DataValue::Null)
+        }
+        v => v.clone(),
+    })
+}
+
 define_op!(OP_IS_IN, 2, false);
 pub(crate) fn op_is_in(args: &[DataValue]) -> Result<DataValue> {
     let left = &args[0];
@@ -256,6 +406,153 @@ pub(crate) fn op_min(args: &[DataValue]) -> Result<DataValue> {
     }
 }
 
+define_op!(OP_GREATEST, 1, true);
+/// The largest non-null argument of any type, or null if all arguments are null.
+pub(crate) fn op_greatest(args: &[DataValue]) -> Result<DataValue> {
+    extreme_non_null(args, Ordering::Greater)
+}
+
+define_op!(OP_LEAST, 1, true);
+/// The smallest non-null argument of any type, or null if all arguments are null.
+pub(crate) fn op_least(args: &[DataValue]) -> Result<DataValue> {
+    extreme_non_null(args, Ordering::Less)
+}
+
+fn extreme_non_null(args: &[DataValue], wanted: Ordering) -> Result<DataValue> {
+    let mut res: Option<&DataValue> = None;
+    for arg in args.iter().filter(|v| **v != DataValue::Null) {
+        match res {
+            None => res = Some(arg),
+            Some(cur) => {
+                ensure_same_value_type(cur, arg)?;
+                if arg.cmp(cur) == wanted {
+                    res = Some(arg)
+                }
+            }
+        }
+    }
+    Ok(res.cloned().unwrap_or(DataValue::Null))
+}
+
+fn flatten_into(collected: &mut Vec<DataValue>, items: &[DataValue], depth: u64) {
+    for item in items {
+        match item {
+            DataValue::List(l) if depth > 0 => flatten_into(collected, l, depth - 1),
+            v => collected.push(v.clone()),
+        }
+    }
+}
+
+define_op!(OP_FLATTEN, 1, true);
+/// Flattens nested lists one level deep, or `depth` levels if a second argument is given.
+pub(crate) fn op_flatten(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(args.len() <= 2, "'flatten' takes at most two arguments");
+    let list = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'flatten' requires a list"))?;
+    let depth = match args.get(1) {
+        None => 1,
+        Some(d) => d
+            .get_non_neg_int()
+            .ok_or_else(|| miette!("'flatten' requires a non-negative integer depth"))?,
+    };
+    let mut collected = Vec::with_capacity(list.len());
+    flatten_into(&mut collected, list, depth);
+    Ok(DataValue::List(collected))
+}
+
+fn select_row_by_key(
+    args: &[DataValue],
+    name: &str,
+    better: std::cmp::Ordering,
+) -> Result<DataValue> {
+    let rows = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'{}' requires a list of rows as its first argument", name))?;
+    let idx = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'{}' requires a non-negative integer key index", name))?
+        as usize;
+    let mut found: Option<(&DataValue, &DataValue)> = None;
+    for row in rows {
+        let cols = row
+            .get_slice()
+            .ok_or_else(|| miette!("'{}' requires every row to be a list, got {:?}", name, row))?;
+        let key = cols.get(idx).ok_or_else(|| {
+            miette!(
+                "'{}': key index {} out of bound for row of length {}",
+                name,
+                idx,
+                cols.len()
+            )
+        })?;
+        match found {
+            // ties keep the earlier row
+            Some((cur, _)) if key.cmp(cur) != better => {}
+            _ => found = Some((key, row)),
+        }
+    }
+    Ok(match found {
+        None => DataValue::Null,
+        Some((_, row)) => row.clone(),
+    })
+}
+
+define_op!(OP_MAX_BY, 2, false);
+pub(crate) fn op_max_by(args: &[DataValue]) -> Result<DataValue> {
+    select_row_by_key(args, "max_by", std::cmp::Ordering::Greater)
+}
+
+define_op!(OP_MIN_BY, 2, false);
+pub(crate) fn op_min_by(args: &[DataValue]) -> Result<DataValue> {
+    select_row_by_key(args, "min_by", std::cmp::Ordering::Less)
+}
+
+fn numeric_list(arg: &DataValue, op_name: &str) -> Result<Vec<f64>> {
+    let list = arg
+        .get_slice()
+        .ok_or_else(|| miette!("'{}' requires a list", op_name))?;
+    ensure!(!list.is_empty(), "'{}' requires a non-empty list", op_name);
+    list.iter()
+        .map(|v| {
+            v.get_float()
+                .ok_or_else(|| miette!("'{}' requires a list of numbers, got {:?}", op_name, v))
+        })
+        .collect()
+}
+
+fn population_variance(xs: &[f64]) -> f64 {
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / xs.len() as f64
+}
+
+define_op!(OP_MEDIAN, 1, false);
+/// For lists of even length the two middle values are averaged.
+pub(crate) fn op_median(args: &[DataValue]) -> Result<DataValue> {
+    let mut xs = numeric_list(&args[0], "median")?;
+    xs.sort_by(|a, b| a.total_cmp(b));
+    let mid = xs.len() / 2;
+    Ok(DataValue::from(if xs.len() % 2 == 0 {
+        (xs[mid - 1] + xs[mid]) / 2.
+    } else {
+        xs[mid]
+    }))
+}
+
+define_op!(OP_VARIANCE, 1, false);
+/// Population variance of a list of numbers.
+pub(crate) fn op_variance(args: &[DataValue]) -> Result<DataValue> {
+    let xs = numeric_list(&args[0], "variance")?;
+    Ok(DataValue::from(population_variance(&xs)))
+}
+
+define_op!(OP_STDDEV, 1, false);
+/// Population standard deviation of a list of numbers.
+pub(crate) fn op_stddev(args: &[DataValue]) -> Result<DataValue> {
+    let xs = numeric_list(&args[0], "stddev")?;
+    Ok(DataValue::from(population_variance(&xs).sqrt()))
+}
+
 define_op!(OP_SUB, 2, false);
 pub(crate) fn op_sub(args: &[DataValue]) -> Result<DataValue> {
     Ok(match (&args[0], &args[1]) {
@@ -369,6 +666,99 @@ pub(crate) fn op_mod(args: &[DataValue]) -> Result<DataValue> {
     })
 }
 
+define_op!(OP_SAFE_MOD, 2, false);
+/// Unlike `mod`, which errors on an integer zero divisor and returns NaN on a float one,
+/// this returns null for a zero divisor of either kind, and for the overflowing
+/// `i64::MIN % -1`.
+pub(crate) fn op_safe_mod(args: &[DataValue]) -> Result<DataValue> {
+    match (&args[0], &args[1]) {
+        (DataValue::Num(Num::Int(a)), DataValue::Num(Num::Int(b))) => Ok(a
+            .checked_rem(*b)
+            .map(DataValue::from)
+            .unwrap_or(DataValue::Null)),
+        (DataValue::Num(_), DataValue::Num(Num::Int(0))) => Ok(DataValue::Null),
+        (DataValue::Num(_), DataValue::Num(Num::Float(f))) if *f == 0. => Ok(DataValue::Null),
+        (DataValue::Num(_), DataValue::Num(_)) => op_mod(args),
+        _ => bail!("'safe_mod' requires numbers"),
+    }
+}
+
+fn int_pair(args: &[DataValue], op_name: &str) -> Result<(i64, i64)> {
+    match (&args[0], &args[1]) {
+        (DataValue::Num(Num::Int(a)), DataValue::Num(Num::Int(b))) => Ok((*a, *b)),
+        _ => bail!("'{}' requires integers", op_name),
+    }
+}
+
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+define_op!(OP_GCD, 2, false);
+/// The result is never negative, and `gcd(0, 0)` is 0.
+pub(crate) fn op_gcd(args: &[DataValue]) -> Result<DataValue> {
+    let (a, b) = int_pair(args, "gcd")?;
+    let gcd = gcd_u64(a.unsigned_abs(), b.unsigned_abs());
+    let gcd = i64::try_from(gcd).map_err(|_| miette!("'gcd' overflowed"))?;
+    Ok(DataValue::from(gcd))
+}
+
+define_op!(OP_LCM, 2, false);
+/// The result is never negative, and is 0 if either operand is 0.
+pub(crate) fn op_lcm(args: &[DataValue]) -> Result<DataValue> {
+    let (a, b) = int_pair(args, "lcm")?;
+    if a == 0 || b == 0 {
+        return Ok(DataValue::from(0));
+    }
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+    let lcm = (a / gcd_u64(a, b))
+        .checked_mul(b)
+        .and_then(|l| i64::try_from(l).ok())
+        .ok_or_else(|| miette!("'lcm' overflowed"))?;
+    Ok(DataValue::from(lcm))
+}
+
+define_op!(OP_FACTORIAL, 1, false);
+/// Only defined while the result fits in an `i64`, i.e. up to `factorial(20)`;
+/// larger values are an error and should be computed with floats instead.
+pub(crate) fn op_factorial(args: &[DataValue]) -> Result<DataValue> {
+    let n = match &args[0] {
+        DataValue::Num(Num::Int(n)) => *n,
+        _ => bail!("'factorial' requires an integer"),
+    };
+    ensure!(n >= 0, "'factorial' requires a non-negative integer, got {}", n);
+    let f = (2..=n)
+        .try_fold(1i64, |acc, i| acc.checked_mul(i))
+        .ok_or_else(|| miette!("'factorial' overflowed for {}", n))?;
+    Ok(DataValue::from(f))
+}
+
+define_op!(OP_BINOMIAL, 2, false);
+/// `binomial(n, k)` is n choose k, and 0 when `k > n`.
+pub(crate) fn op_binomial(args: &[DataValue]) -> Result<DataValue> {
+    let (n, k) = int_pair(args, "binomial")?;
+    ensure!(
+        n >= 0 && k >= 0,
+        "'binomial' requires non-negative integers, got {} and {}",
+        n,
+        k
+    );
+    if k > n {
+        return Ok(DataValue::from(0));
+    }
+    let k = k.min(n - k);
+    // each partial product is itself a binomial coefficient, so the division is exact
+    let mut acc: i128 = 1;
+    for i in 0..k {
+        acc = acc * (n - i) as i128 / (i + 1) as i128;
+        ensure!(acc <= i64::MAX as i128, "'binomial' overflowed for {} and {}", n, k);
+    }
+    Ok(DataValue::from(acc as i64))
+}
+
 define_op!(OP_AND, 0, true);
 pub(crate) fn op_and(args: &[DataValue]) -> Result<DataValue> {
     for arg in args {
@@ -404,7 +794,7 @@ pub(crate) fn op_negate(args: &[DataValue]) -> Result<DataValue> {
     }
 }
 
-fn deep_merge_json(value1: JsonValue, value2: JsonValue) -> JsonValue {
+pub(crate) fn deep_merge_json(value1: JsonValue, value2: JsonValue) -> JsonValue {
     match (value1, value2) {
         (JsonValue::Object(mut obj1), JsonValue::Object(obj2)) => {
             for (key, value2) in obj2 {
@@ -422,6 +812,460 @@ fn deep_merge_json(value1: JsonValue, value2: JsonValue) -> JsonValue {
 }
 
 
+define_op!(OP_ASSOC_GET, 2, true);
+/// Looks up `key` in a list of `[key, value]` pairs, returning the value of the first
+/// matching pair, or else the optional third argument or null.
+pub(crate) fn op_assoc_get(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(args.len() <= 3, "'assoc_get' takes at most three arguments");
+    let pairs = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'assoc_get' requires a list of pairs"))?;
+    let mut found = None;
+    for pair in pairs {
+        match pair.get_slice() {
+            Some([k, v]) => {
+                if found.is_none() && *k == args[1] {
+                    found = Some(v);
+                }
+            }
+            _ => bail!("'assoc_get' requires a list of pairs, got element {:?}", pair),
+        }
+    }
+    Ok(found
+        .or(args.get(2))
+        .cloned()
+        .unwrap_or(DataValue::Null))
+}
+
+define_op!(OP_SLICE_STEP, 4, false);
+/// Every `stride`-th element of `list[start..end]`, where negative indices count from the end.
+pub(crate) fn op_slice_step(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'slice_step' requires a list"))?;
+    let start = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'slice_step' requires integer indices"))?;
+    let end = args[2]
+        .get_int()
+        .ok_or_else(|| miette!("'slice_step' requires integer indices"))?;
+    let stride = args[3]
+        .get_int()
+        .ok_or_else(|| miette!("'slice_step' requires an integer stride"))?;
+    ensure!(stride > 0, "'slice_step' requires a positive stride, got {}", stride);
+    let start = get_index(start, l.len(), false)?;
+    let end = get_index(end, l.len(), true)?;
+    if start >= end {
+        return Ok(DataValue::List(vec![]));
+    }
+    Ok(DataValue::List(
+        l[start..end].iter().step_by(stride as usize).cloned().collect(),
+    ))
+}
+
+define_op!(OP_LIST_SET, 3, false);
+/// A copy of `list` with the element at `idx` replaced by `val`; negative indices count from the end.
+pub(crate) fn op_list_set(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'list_set' requires a list"))?;
+    let idx = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'list_set' requires an integer index"))?;
+    let idx = get_index(idx, l.len(), false)?;
+    let mut res = l.to_vec();
+    res[idx] = args[2].clone();
+    Ok(DataValue::List(res))
+}
+
+define_op!(OP_LIST_INSERT, 3, false);
+/// A copy of `list` with `val` inserted before `idx`; an index equal to the length appends.
+pub(crate) fn op_list_insert(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'list_insert' requires a list"))?;
+    let idx = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'list_insert' requires an integer index"))?;
+    let idx = get_index(idx, l.len(), true)?;
+    let mut res = l.to_vec();
+    res.insert(idx, args[2].clone());
+    Ok(DataValue::List(res))
+}
+
+define_op!(OP_LIST_REMOVE, 2, false);
+/// A copy of `list` without the element at `idx`; negative indices count from the end.
+pub(crate) fn op_list_remove(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'list_remove' requires a list"))?;
+    let idx = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'list_remove' requires an integer index"))?;
+    let idx = get_index(idx, l.len(), false)?;
+    let mut res = l.to_vec();
+    res.remove(idx);
+    Ok(DataValue::List(res))
+}
+
+define_op!(OP_FIELD, 2, false);
+/// The element at position `idx` of a row stored as a list. Unlike list indexing elsewhere,
+/// negative indices are rejected, and an index past the end is an error rather than null.
+pub(crate) fn op_field(args: &[DataValue]) -> Result<DataValue> {
+    let row = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'field' requires a list"))?;
+    let idx = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'field' requires a non-negative integer index"))?;
+    row.get(idx as usize).cloned().ok_or_else(|| {
+        miette!(
+            "'field' index {} is out of range for a row of arity {}",
+            idx,
+            row.len()
+        )
+    })
+}
+
+/// The largest count `fill` accepts, so that a stray huge count fails with an error
+/// instead of aborting on allocation.
+const MAX_FILL_COUNT: u64 = 1 << 20;
+
+define_op!(OP_FILL, 2, false);
+/// A list of `n` copies of `value`, with `n` at most [MAX_FILL_COUNT]. Each element is a
+/// separate clone of `value`, so nothing is shared between them.
+pub(crate) fn op_fill(args: &[DataValue]) -> Result<DataValue> {
+    let n = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'fill' requires a non-negative integer count"))?;
+    ensure!(
+        n <= MAX_FILL_COUNT,
+        "'fill' count must be at most {}, got {}",
+        MAX_FILL_COUNT,
+        n
+    );
+    Ok(DataValue::List(vec![args[0].clone(); n as usize]))
+}
+
+define_op!(OP_PREFIX_SUM, 1, false);
+/// Running totals of a list of numbers, adding as `add` does: the totals stay integers
+/// until a float is added.
+pub(crate) fn op_prefix_sum(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'prefix_sum' requires a list"))?;
+    let mut total = DataValue::from(0);
+    let mut res = Vec::with_capacity(l.len());
+    for v in l {
+        ensure!(
+            matches!(v, DataValue::Num(_)),
+            "'prefix_sum' requires a list of numbers, got {:?}",
+            v
+        );
+        total = op_add(&[total, v.clone()])?;
+        res.push(total.clone());
+    }
+    Ok(DataValue::List(res))
+}
+
+define_op!(OP_DIFF, 1, false);
+/// Differences between consecutive elements of a list of numbers, subtracting as `sub`
+/// does; the inverse of `prefix_sum`. Lists with fewer than two elements give an empty list.
+pub(crate) fn op_diff(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'diff' requires a list"))?;
+    if let Some(v) = l.iter().find(|v| !matches!(v, DataValue::Num(_))) {
+        bail!("'diff' requires a list of numbers, got {:?}", v)
+    }
+    Ok(DataValue::List(
+        l.iter()
+            .tuple_windows()
+            .map(|(a, b)| op_sub(&[b.clone(), a.clone()]))
+            .try_collect()?,
+    ))
+}
+
+/// Clamps `n` into `[low, high]`, comparing numerically so that an integer equal to a float
+/// bound is left as it is.
+fn clamp_num(n: Num, low: Num, high: Num) -> Num {
+    if n.get_float() < low.get_float() {
+        low
+    } else if n.get_float() > high.get_float() {
+        high
+    } else {
+        n
+    }
+}
+
+define_op!(OP_CLAMP_LIST, 3, false);
+/// Clamps every number in a list into `[low, high]`.
+pub(crate) fn op_clamp_list(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'clamp_list' requires a list"))?;
+    let (low, high) = match (&args[1], &args[2]) {
+        (DataValue::Num(low), DataValue::Num(high)) => (*low, *high),
+        _ => bail!("'clamp_list' requires numeric bounds"),
+    };
+    ensure!(
+        low.get_float() <= high.get_float(),
+        "'clamp_list' requires the lower bound not to exceed the upper bound, got {:?} and {:?}",
+        low,
+        high
+    );
+    Ok(DataValue::List(
+        l.iter()
+            .map(|v| match v {
+                DataValue::Num(n) => Ok(DataValue::Num(clamp_num(*n, low, high))),
+                v => bail!("'clamp_list' requires a list of numbers, got {:?}", v),
+            })
+            .try_collect()?,
+    ))
+}
+
+define_op!(OP_COSINE_SIMILARITY, 2, false);
+/// The cosine of the angle between two equal-length vectors of numbers, in `[-1, 1]`.
+/// The angle is undefined if either vector is all zeros, and null is returned then.
+pub(crate) fn op_cosine_similarity(args: &[DataValue]) -> Result<DataValue> {
+    let as_floats = |v: &DataValue| -> Result<Vec<f64>> {
+        v.get_slice()
+            .ok_or_else(|| miette!("'cosine_similarity' requires two lists"))?
+            .iter()
+            .map(|x| {
+                x.get_float()
+                    .ok_or_else(|| miette!("'cosine_similarity' requires lists of numbers"))
+            })
+            .try_collect()
+    };
+    let a = as_floats(&args[0])?;
+    let b = as_floats(&args[1])?;
+    ensure!(
+        a.len() == b.len(),
+        "'cosine_similarity' requires lists of the same length, got {} and {}",
+        a.len(),
+        b.len()
+    );
+    let dot: f64 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+    let norm_a: f64 = a.iter().map(|x| x * x).sum();
+    let norm_b: f64 = b.iter().map(|x| x * x).sum();
+    if norm_a == 0. || norm_b == 0. {
+        return Ok(DataValue::Null);
+    }
+    Ok(DataValue::from((dot / (norm_a * norm_b).sqrt()).clamp(-1., 1.)))
+}
+
+define_op!(OP_REPLACE_NULL, 2, true);
+/// A copy of `list` with every null element replaced by `val`. With a true third argument,
+/// nulls inside nested lists are replaced as well.
+pub(crate) fn op_replace_null(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(args.len() <= 3, "'replace_null' takes at most three arguments");
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'replace_null' requires a list"))?;
+    let recursive = match args.get(2) {
+        None => false,
+        Some(v) => v
+            .get_bool()
+            .ok_or_else(|| miette!("'replace_null' requires a boolean to recurse"))?,
+    };
+    Ok(replace_null(l, &args[1], recursive))
+}
+
+fn replace_null(l: &[DataValue], val: &DataValue, recursive: bool) -> DataValue {
+    DataValue::List(
+        l.iter()
+            .map(|el| match el {
+                DataValue::Null => val.clone(),
+                DataValue::List(inner) if recursive => replace_null(inner, val, true),
+                el => el.clone(),
+            })
+            .collect(),
+    )
+}
+
+define_op!(OP_TRANSPOSE, 1, false);
+/// Turns a list of equal-length rows into the list of its columns.
+pub(crate) fn op_transpose(args: &[DataValue]) -> Result<DataValue> {
+    let rows = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'transpose' requires a list of lists"))?;
+    let rows: Vec<&[DataValue]> = rows
+        .iter()
+        .map(|row| {
+            row.get_slice()
+                .ok_or_else(|| miette!("'transpose' requires a list of lists, got element {:?}", row))
+        })
+        .try_collect()?;
+    let Some(width) = rows.first().map(|row| row.len()) else {
+        return Ok(DataValue::List(vec![]));
+    };
+    if let Some((idx, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+        bail!(
+            "'transpose' requires rows of equal length, but row {} has length {} instead of {}",
+            idx,
+            row.len(),
+            width
+        )
+    }
+    Ok(DataValue::List(
+        (0..width)
+            .map(|col| DataValue::List(rows.iter().map(|row| row[col].clone()).collect()))
+            .collect(),
+    ))
+}
+
+define_op!(OP_UNIQ_COUNT, 1, false);
+/// The number of distinct elements in a list.
+pub(crate) fn op_uniq_count(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'uniq_count' requires a list"))?;
+    Ok(DataValue::from(l.iter().collect::<BTreeSet<_>>().len() as i64))
+}
+
+define_op!(OP_HASH, 1, false);
+/// A stable 64-bit FNV-1a hash of any value, reproducible across runs.
+/// Structurally equal values hash equally; as with `eq`, an integral float
+/// hashes the same as the corresponding integer.
+pub(crate) fn op_hash(args: &[DataValue]) -> Result<DataValue> {
+    let mut hasher = StableHasher::default();
+    hasher.value(&args[0]);
+    Ok(DataValue::from(hasher.0 as i64))
+}
+
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn tag(&mut self, tag: u8) {
+        self.write(&[tag]);
+    }
+    fn len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.write(s.as_bytes());
+    }
+    fn float(&mut self, f: f64) {
+        if f.fract() == 0. && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+            self.int(f as i64)
+        } else {
+            let f = if f.is_nan() { f64::NAN } else { f };
+            self.tag(3);
+            self.write(&f.to_bits().to_le_bytes());
+        }
+    }
+    fn int(&mut self, i: i64) {
+        self.tag(2);
+        self.write(&i.to_le_bytes());
+    }
+    fn value(&mut self, v: &DataValue) {
+        match v {
+            DataValue::Null => self.tag(0),
+            DataValue::Bool(b) => self.write(&[1, *b as u8]),
+            DataValue::Num(Num::Int(i)) => self.int(*i),
+            DataValue::Num(Num::Float(f)) => self.float(*f),
+            DataValue::Str(s) => {
+                self.tag(4);
+                self.str(s);
+            }
+            DataValue::Bytes(b) => {
+                self.tag(5);
+                self.len(b.len());
+                self.write(b);
+            }
+            DataValue::Uuid(u) => {
+                self.tag(6);
+                self.write(u.0.as_bytes());
+            }
+            DataValue::List(l) => {
+                self.tag(7);
+                self.len(l.len());
+                l.iter().for_each(|v| self.value(v));
+            }
+            DataValue::Set(s) => {
+                self.tag(8);
+                self.len(s.len());
+                s.iter().for_each(|v| self.value(v));
+            }
+            DataValue::Json(j) => {
+                self.tag(9);
+                self.json(&j.0);
+            }
+            DataValue::Validity(vld) => {
+                self.tag(10);
+                self.write(&vld.timestamp.0 .0.to_le_bytes());
+                self.write(&[vld.is_assert.0 as u8]);
+            }
+            DataValue::Bot => self.tag(11),
+        }
+    }
+    fn json(&mut self, j: &JsonValue) {
+        match j {
+            JsonValue::Null => self.tag(0),
+            JsonValue::Bool(b) => self.write(&[1, *b as u8]),
+            JsonValue::Number(n) => match n.as_i64() {
+                Some(i) => self.int(i),
+                None => self.float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            JsonValue::String(s) => {
+                self.tag(4);
+                self.str(s);
+            }
+            JsonValue::Array(arr) => {
+                self.tag(7);
+                self.len(arr.len());
+                arr.iter().for_each(|v| self.json(v));
+            }
+            JsonValue::Object(obj) => {
+                self.tag(12);
+                self.len(obj.len());
+                for (k, v) in obj.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+                    self.str(k);
+                    self.json(v);
+                }
+            }
+        }
+    }
+}
+
+define_op!(OP_SHA256, 1, false);
+/// The SHA-256 digest of a string (as UTF-8) or bytes, as lowercase hex.
+pub(crate) fn op_sha256(args: &[DataValue]) -> Result<DataValue> {
+    let digest = match &args[0] {
+        DataValue::Str(s) => Sha256::digest(s.as_bytes()),
+        DataValue::Bytes(b) => Sha256::digest(b),
+        _ => bail!("'sha256' requires a string or bytes"),
+    };
+    Ok(DataValue::from(
+        digest.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+    ))
+}
+
+define_op!(OP_CRC32, 1, false);
+/// The CRC-32 checksum of a string (as UTF-8) or bytes.
+pub(crate) fn op_crc32(args: &[DataValue]) -> Result<DataValue> {
+    let checksum = match &args[0] {
+        DataValue::Str(s) => crc32fast::hash(s.as_bytes()),
+        DataValue::Bytes(b) => crc32fast::hash(b),
+        _ => bail!("'crc32' requires a string or bytes"),
+    };
+    Ok(DataValue::from(checksum as i64))
+}
+
 fn get_index(mut i: i64, total: usize, is_upper: bool) -> Result<usize> {
     if i < 0 {
         i += total as i64;
@@ -439,7 +1283,7 @@ fn get_index(mut i: i64, total: usize, is_upper: bool) -> Result<usize> {
 }
 
 
-fn json2val(res: Value) -> DataValue {
+pub(crate) fn json2val(res: Value) -> DataValue {
     match res {
         Value::Null => DataValue::Null,
         Value::Bool(b) => DataValue::Bool(b),
@@ -458,6 +1302,219 @@ fn json2val(res: Value) -> DataValue {
     }
 }
 
+/// Like `json2val`, but arrays become lists all the way down.
+pub(crate) fn json2val_deep(res: &Value) -> DataValue {
+    match res {
+        Value::Array(arr) => DataValue::List(arr.iter().map(json2val_deep).collect()),
+        v => json2val(v.clone()),
+    }
+}
+
+define_op!(OP_LIST_TO_JSON, 1, false);
+pub(crate) fn op_list_to_json(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(
+        matches!(args[0], DataValue::List(_)),
+        "'list_to_json' requires a list"
+    );
+    Ok(DataValue::Json(JsonData(to_json(&args[0]))))
+}
+
+define_op!(OP_JSON_TO_LIST, 1, false);
+/// Nested arrays become nested lists, while objects stay as JSON.
+pub(crate) fn op_json_to_list(args: &[DataValue]) -> Result<DataValue> {
+    match &args[0] {
+        DataValue::Json(JsonData(arr @ JsonValue::Array(_))) => Ok(json2val_deep(arr)),
+        _ => bail!("'json_to_list' requires a JSON array"),
+    }
+}
+
+define_op!(OP_JSON_PATH_GET, 2, false);
+/// Follows a JSON pointer (RFC 6901) such as `"/a/0/b"` into a JSON value, with `~1` and `~0`
+/// standing for `/` and `~` inside keys. Missing paths give null.
+pub(crate) fn op_json_path_get(args: &[DataValue]) -> Result<DataValue> {
+    let json = match &args[0] {
+        DataValue::Json(JsonData(json)) => json,
+        _ => bail!("'json_path_get' requires a JSON value"),
+    };
+    let path = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'json_path_get' requires a string path"))?;
+    ensure!(
+        path.is_empty() || path.starts_with('/'),
+        "'json_path_get' requires a path starting with '/', got {:?}",
+        path
+    );
+    Ok(json
+        .pointer(path)
+        .map(|found| json2val(found.clone()))
+        .unwrap_or(DataValue::Null))
+}
+
+fn sorted_json_object<'a>(
+    name: &str,
+    arg: &'a DataValue,
+) -> Result<Vec<(&'a String, &'a JsonValue)>> {
+    match arg {
+        DataValue::Json(JsonData(JsonValue::Object(obj))) => {
+            Ok(obj.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)).collect())
+        }
+        _ => bail!("'{}' requires a JSON object", name),
+    }
+}
+
+define_op!(OP_JSON_KEYS, 1, false);
+/// The keys of a JSON object, in sorted order.
+pub(crate) fn op_json_keys(args: &[DataValue]) -> Result<DataValue> {
+    let entries = sorted_json_object("json_keys", &args[0])?;
+    Ok(DataValue::List(
+        entries.into_iter().map(|(k, _)| DataValue::from(k.as_str())).collect(),
+    ))
+}
+
+define_op!(OP_JSON_VALUES, 1, false);
+/// The values of a JSON object, in the order of their sorted keys.
+pub(crate) fn op_json_values(args: &[DataValue]) -> Result<DataValue> {
+    let entries = sorted_json_object("json_values", &args[0])?;
+    Ok(DataValue::List(
+        entries.into_iter().map(|(_, v)| json2val(v.clone())).collect(),
+    ))
+}
+
+/// Splits a JSON pointer (RFC 6901) into its unescaped reference tokens.
+fn json_pointer_tokens(name: &str, path: &str) -> Result<Vec<String>> {
+    ensure!(
+        path.is_empty() || path.starts_with('/'),
+        "'{}' requires a path starting with '/', got {:?}",
+        name,
+        path
+    );
+    Ok(path
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+define_op!(OP_JSON_SET, 3, false);
+/// A copy of a JSON value with the location at a JSON pointer set to a new value. Missing
+/// object keys along the way are created as objects, and the index one past the end of an
+/// array (or `-`) appends to it.
+pub(crate) fn op_json_set(args: &[DataValue]) -> Result<DataValue> {
+    let mut json = match &args[0] {
+        DataValue::Json(JsonData(json)) => json.clone(),
+        _ => bail!("'json_set' requires a JSON value"),
+    };
+    let path = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'json_set' requires a string path"))?;
+    let tokens = json_pointer_tokens("json_set", path)?;
+    let mut current = &mut json;
+    for (i, token) in tokens.iter().enumerate() {
+        let is_last = i == tokens.len() - 1;
+        current = match current {
+            JsonValue::Object(obj) => obj.entry(token.clone()).or_insert_with(|| {
+                if is_last {
+                    JsonValue::Null
+                } else {
+                    json!({})
+                }
+            }),
+            JsonValue::Array(arr) => {
+                let idx = match token.as_str() {
+                    "-" => arr.len(),
+                    t => t.parse::<usize>().map_err(|_| {
+                        miette!("'json_set' cannot index an array with {:?}", token)
+                    })?,
+                };
+                ensure!(
+                    idx < arr.len() || (is_last && idx == arr.len()),
+                    "'json_set' index {} is out of bounds for an array of length {}",
+                    idx,
+                    arr.len()
+                );
+                if idx == arr.len() {
+                    arr.push(JsonValue::Null);
+                }
+                &mut arr[idx]
+            }
+            v => bail!("'json_set' cannot follow {:?} into {}", token, v),
+        };
+    }
+    *current = to_json(&args[2]);
+    Ok(DataValue::Json(JsonData(json)))
+}
+
+define_op!(OP_JSON_REMOVE, 2, false);
+/// A copy of a JSON value with the object key or array element at a JSON pointer removed;
+/// later array elements shift down. A path that leads nowhere leaves the value unchanged.
+pub(crate) fn op_json_remove(args: &[DataValue]) -> Result<DataValue> {
+    let mut json = match &args[0] {
+        DataValue::Json(JsonData(json)) => json.clone(),
+        _ => bail!("'json_remove' requires a JSON value"),
+    };
+    let path = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'json_remove' requires a string path"))?;
+    let mut tokens = json_pointer_tokens("json_remove", path)?;
+    let Some(last) = tokens.pop() else {
+        bail!("'json_remove' cannot remove the whole value")
+    };
+    let mut current = &mut json;
+    for token in &tokens {
+        let next = match current {
+            JsonValue::Object(obj) => obj.get_mut(token),
+            JsonValue::Array(arr) => token.parse::<usize>().ok().and_then(|i| arr.get_mut(i)),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(args[0].clone()),
+        }
+    }
+    match current {
+        JsonValue::Object(obj) => {
+            obj.remove(&last);
+        }
+        JsonValue::Array(arr) => {
+            if let Some(i) = last.parse::<usize>().ok().filter(|i| *i < arr.len()) {
+                arr.remove(i);
+            }
+        }
+        _ => {}
+    }
+    Ok(DataValue::Json(JsonData(json)))
+}
+
+define_op!(OP_JSON_DEEP_COPY, 1, false);
+/// A copy of a JSON value sharing no structure with the original. JSON values own their
+/// contents, so this is a plain clone; non-JSON values are passed through unchanged.
+pub(crate) fn op_json_deep_copy(args: &[DataValue]) -> Result<DataValue> {
+    Ok(match &args[0] {
+        DataValue::Json(JsonData(json)) => DataValue::Json(JsonData(json.clone())),
+        v => v.clone(),
+    })
+}
+
+define_op!(OP_PRETTY_JSON, 1, true);
+/// Serializes a value as indented JSON, by default two spaces per level; an optional second
+/// argument gives the indent width.
+pub(crate) fn op_pretty_json(args: &[DataValue]) -> Result<DataValue> {
+    ensure!(args.len() <= 2, "'pretty_json' takes at most 2 arguments");
+    let width = match args.get(1) {
+        None => 2,
+        Some(w) => w
+            .get_non_neg_int()
+            .ok_or_else(|| miette!("'pretty_json' requires a non-negative indent width"))?
+            as usize,
+    };
+    let indent = " ".repeat(width);
+    let mut out = vec![];
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    serde::Serialize::serialize(&to_json(&args[0]), &mut serializer).into_diagnostic()?;
+    Ok(DataValue::from(String::from_utf8(out).into_diagnostic()?))
+}
+
 define_op!(OP_TO_STRING, 1, false);
 pub(crate) fn op_to_string(args: &[DataValue]) -> Result<DataValue> {
     Ok(DataValue::Str(val2str(&args[0]).into()))
@@ -474,6 +1531,446 @@ fn val2str(arg: &DataValue) -> String {
     }
 }
 
+define_op!(OP_TO_FLOAT, 1, false);
+pub(crate) fn op_to_float(args: &[DataValue]) -> Result<DataValue> {
+    Ok(match &args[0] {
+        DataValue::Num(n) => n.get_float().into(),
+        DataValue::Str(t) => {
+            // `f64::from_str` also takes spellings such as "inf" and "NaN", which are not
+            // numeric strings
+            let f = f64::from_str(t.trim())
+                .ok()
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| miette!("The string cannot be interpreted as float"))?;
+            f.into()
+        }
+        v => bail!("'to_float' does not recognize {:?}", v),
+    })
+}
+
+define_op!(OP_PARSE_FLOAT_LOCALE, 2, false);
+/// Parses a number written with the given decimal separator, `.` or `,`. The other of the
+/// two may group the integer digits in threes, so `parse_float_locale('1.234,56', ',')`
+/// gives `1234.56`.
+pub(crate) fn op_parse_float_locale(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'parse_float_locale' requires a string"))?;
+    let (decimal, thousands) = match args[1].get_str() {
+        Some(".") => ('.', ','),
+        Some(",") => (',', '.'),
+        _ => bail!("'parse_float_locale' requires '.' or ',' as the decimal separator"),
+    };
+    let trimmed = s.trim();
+    let (sign, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (int_part, frac_part) = unsigned.split_once(decimal).unwrap_or((unsigned, ""));
+    let groups = int_part.split(thousands).collect_vec();
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let well_grouped = groups.len() == 1
+        || ((1..=3).contains(&groups[0].len()) && groups[1..].iter().all(|g| g.len() == 3));
+    ensure!(
+        groups.iter().all(|g| is_digits(g))
+            && is_digits(frac_part)
+            && well_grouped
+            && !(int_part.is_empty() && frac_part.is_empty()),
+        "'parse_float_locale' cannot interpret {:?} as a number with decimal separator '{}'",
+        s,
+        decimal
+    );
+    let normalized = format!("{sign}{}.{frac_part}", groups.concat());
+    let f = f64::from_str(&normalized).into_diagnostic()?;
+    Ok(DataValue::from(f))
+}
+
+define_op!(OP_TO_INT, 1, false);
+/// Floats are truncated toward zero, so `to_int(-2.7)` gives `-2`. Floats outside the range
+/// of a 64-bit integer are an error rather than being clamped to it.
+pub(crate) fn op_to_int(args: &[DataValue]) -> Result<DataValue> {
+    Ok(match &args[0] {
+        DataValue::Num(Num::Int(i)) => DataValue::Num(Num::Int(*i)),
+        DataValue::Num(Num::Float(f)) => {
+            let t = f.trunc();
+            // `i64::MAX as f64` rounds up to 2^63, which is itself out of range
+            ensure!(
+                t >= i64::MIN as f64 && t < i64::MAX as f64,
+                "'to_int' cannot convert {} to an integer",
+                f
+            );
+            DataValue::Num(Num::Int(t as i64))
+        }
+        DataValue::Str(t) => i64::from_str(t.trim())
+            .map_err(|_| miette!("The string cannot be interpreted as int"))?
+            .into(),
+        v => bail!("'to_int' does not recognize {:?}", v),
+    })
+}
+
+lazy_static! {
+    static ref REGEX_CACHE: Mutex<BTreeMap<String, Regex>> = Mutex::new(BTreeMap::new());
+}
+
+const REGEX_CACHE_CAPACITY: usize = 256;
+
+/// Compiles `pattern`, reusing a previous compilation of the same pattern if there is one.
+fn get_regex(op_name: &str, pattern: &DataValue) -> Result<Regex> {
+    let pattern = pattern
+        .get_str()
+        .ok_or_else(|| miette!("'{}' requires a string pattern", op_name))?;
+    let mut cache = REGEX_CACHE.lock().unwrap();
+    if let Some(re) = cache.get(pattern) {
+        return Ok(re.clone());
+    }
+    let re = Regex::new(pattern)
+        .map_err(|err| miette!("'{}': invalid regex {:?}: {}", op_name, pattern, err))?;
+    if cache.len() >= REGEX_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), re.clone());
+    Ok(re)
+}
+
+define_op!(OP_REGEX_MATCHES, 2, false);
+pub(crate) fn op_regex_matches(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'regex_matches' requires a string"))?;
+    let re = get_regex("regex_matches", &args[1])?;
+    Ok(DataValue::from(re.is_match(s)))
+}
+
+define_op!(OP_REGEX_EXTRACT, 2, false);
+/// Returns the first capture group of the first match, or the whole match if
+/// the pattern has no groups.
+pub(crate) fn op_regex_extract(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'regex_extract' requires a string"))?;
+    let re = get_regex("regex_extract", &args[1])?;
+    Ok(match re.captures(s) {
+        None => DataValue::Null,
+        Some(caps) => {
+            let m = if caps.len() > 1 { caps.get(1) } else { caps.get(0) };
+            match m {
+                None => DataValue::Null,
+                Some(m) => DataValue::from(m.as_str()),
+            }
+        }
+    })
+}
+
+define_op!(OP_REGEX_REPLACE, 3, false);
+/// Replaces all matches; the replacement may refer to groups as `$1` or `${name}`.
+pub(crate) fn op_regex_replace(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'regex_replace' requires a string"))?;
+    let re = get_regex("regex_replace", &args[1])?;
+    let replacement = args[2]
+        .get_str()
+        .ok_or_else(|| miette!("'regex_replace' requires a string replacement"))?;
+    Ok(DataValue::from(re.replace_all(s, replacement).into_owned()))
+}
+
+define_op!(OP_NUMBER_FORMAT, 2, false);
+/// Exact ties round to the even digit, e.g. `number_format(0.125, 2)` gives `"0.12"`.
+pub(crate) fn op_number_format(args: &[DataValue]) -> Result<DataValue> {
+    let f = args[0]
+        .get_float()
+        .ok_or_else(|| miette!("'number_format' requires a number"))?;
+    let precision = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'number_format' requires a non-negative integer precision"))?;
+    Ok(DataValue::from(format!("{:.*}", precision as usize, f)))
+}
+
+/// The largest width `zero_fill` pads to.
+const MAX_ZERO_FILL_WIDTH: u64 = 1 << 20;
+
+define_op!(OP_ZERO_FILL, 2, false);
+/// The width includes the sign, which stays in front of the zeros: `zero_fill(-42, 5)` gives `"-0042"`.
+/// It is at most [MAX_ZERO_FILL_WIDTH].
+pub(crate) fn op_zero_fill(args: &[DataValue]) -> Result<DataValue> {
+    let (negative, digits) = match &args[0] {
+        DataValue::Num(Num::Int(i)) => (*i < 0, i.unsigned_abs().to_string()),
+        DataValue::Str(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s as &str),
+            };
+            ensure!(
+                !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()),
+                "'zero_fill' requires an integer, got {:?}",
+                s
+            );
+            (negative, digits.to_string())
+        }
+        v => bail!("'zero_fill' requires an integer, got {:?}", v),
+    };
+    let width = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'zero_fill' requires a non-negative integer width"))?;
+    ensure!(
+        width <= MAX_ZERO_FILL_WIDTH,
+        "'zero_fill' width must be at most {}, got {}",
+        MAX_ZERO_FILL_WIDTH,
+        width
+    );
+    let width = width as usize;
+    let sign = if negative { "-" } else { "" };
+    let n_zeros = width.saturating_sub(sign.len() + digits.len());
+    Ok(DataValue::from(format!("{}{}{}", sign, "0".repeat(n_zeros), digits)))
+}
+
+define_op!(OP_CHARS, 1, false);
+pub(crate) fn op_chars(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'chars' requires a string"))?;
+    Ok(DataValue::List(
+        s.chars().map(|c| DataValue::from(c.to_string())).collect(),
+    ))
+}
+
+define_op!(OP_BYTE_LENGTH, 1, false);
+/// The length of a string in UTF-8 bytes, rather than in characters.
+pub(crate) fn op_byte_length(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'byte_length' requires a string"))?;
+    Ok(DataValue::from(s.len() as i64))
+}
+
+define_op!(OP_COUNT_SUBSTR, 2, false);
+/// Counts non-overlapping occurrences, scanning from the left: `count_substr("aaa", "aa")` is 1.
+pub(crate) fn op_count_substr(args: &[DataValue]) -> Result<DataValue> {
+    let haystack = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'count_substr' requires strings"))?;
+    let needle = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'count_substr' requires strings"))?;
+    ensure!(!needle.is_empty(), "'count_substr' requires a non-empty needle");
+    Ok(DataValue::from(haystack.matches(needle).count() as i64))
+}
+
+define_op!(OP_POSITIONS, 2, false);
+/// All indices at which `x` occurs in a list, or, for strings, the start of every
+/// non-overlapping occurrence of a substring, counted in Unicode scalars.
+pub(crate) fn op_positions(args: &[DataValue]) -> Result<DataValue> {
+    let positions = match (&args[0], &args[1]) {
+        (DataValue::List(l), x) => l
+            .iter()
+            .enumerate()
+            .filter(|(_, el)| *el == x)
+            .map(|(i, _)| DataValue::from(i as i64))
+            .collect_vec(),
+        (DataValue::Str(haystack), DataValue::Str(needle)) => {
+            ensure!(!needle.is_empty(), "'positions' requires a non-empty substring");
+            let mut scalars_before = 0;
+            let mut scanned = 0;
+            haystack
+                .match_indices(needle.as_str())
+                .map(|(byte_idx, _)| {
+                    scalars_before += haystack[scanned..byte_idx].chars().count();
+                    scanned = byte_idx;
+                    DataValue::from(scalars_before as i64)
+                })
+                .collect_vec()
+        }
+        _ => bail!("'positions' requires a list, or a string and a substring"),
+    };
+    Ok(DataValue::List(positions))
+}
+
+define_op!(OP_LEVENSHTEIN, 2, false);
+/// Edit distance counted in Unicode scalars.
+pub(crate) fn op_levenshtein(args: &[DataValue]) -> Result<DataValue> {
+    let a = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'levenshtein' requires strings"))?;
+    let b = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'levenshtein' requires strings"))?;
+    let a = a.chars().collect_vec();
+    let b = b.chars().collect_vec();
+    let mut prev = (0..=b.len()).collect_vec();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        mem::swap(&mut prev, &mut cur);
+    }
+    Ok(DataValue::from(prev[b.len()] as i64))
+}
+
+define_op!(OP_TRIGRAM_SIMILARITY, 2, false);
+/// Jaccard similarity of the character trigrams of both strings, in `[0, 1]`.
+/// Strings are padded with two spaces in front and one behind, so that short strings
+/// still produce trigrams and word boundaries weigh in.
+pub(crate) fn op_trigram_similarity(args: &[DataValue]) -> Result<DataValue> {
+    let a = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'trigram_similarity' requires strings"))?;
+    let b = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'trigram_similarity' requires strings"))?;
+    let a = trigrams(a);
+    let b = trigrams(b);
+    let shared = a.intersection(&b).count();
+    let total = a.union(&b).count();
+    Ok(DataValue::from(shared as f64 / total as f64))
+}
+
+fn trigrams(s: &str) -> BTreeSet<[char; 3]> {
+    let chars = "  ".chars().chain(s.chars()).chain(" ".chars()).collect_vec();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+define_op!(OP_WRAP, 2, false);
+/// Wraps text at word boundaries into lines of at most `width` Unicode scalars, breaking words
+/// that do not fit on a line of their own. Runs of whitespace, including newlines, are collapsed.
+pub(crate) fn op_wrap(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'wrap' requires a string"))?;
+    let width = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'wrap' requires an integer width"))?;
+    ensure!(width > 0, "'wrap' requires a positive width, got {}", width);
+    let width = width as usize;
+
+    let mut lines: Vec<String> = vec![];
+    let mut cur = String::new();
+    let mut cur_len = 0;
+    for word in s.split_whitespace() {
+        let word = word.chars().collect_vec();
+        if cur_len > 0 && cur_len + 1 + word.len() <= width {
+            cur.push(' ');
+            cur.extend(&word);
+            cur_len += 1 + word.len();
+            continue;
+        }
+        if cur_len > 0 {
+            lines.push(mem::take(&mut cur));
+        }
+        let mut chunks = word.chunks(width).peekable();
+        while let Some(chunk) = chunks.next() {
+            if chunks.peek().is_some() {
+                lines.push(chunk.iter().collect());
+            } else {
+                cur = chunk.iter().collect();
+                cur_len = chunk.len();
+            }
+        }
+    }
+    if cur_len > 0 {
+        lines.push(cur);
+    }
+    Ok(DataValue::from(lines.join("\n")))
+}
+
+define_op!(OP_LINES, 1, false);
+/// Splits a string on `\n` or `\r\n`. As with [str::lines], a trailing line break does not
+/// produce a trailing empty line, and the empty string has no lines.
+pub(crate) fn op_lines(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'lines' requires a string"))?;
+    Ok(DataValue::List(s.lines().map(DataValue::from).collect()))
+}
+
+define_op!(OP_UNLINES, 1, false);
+/// Joins a list of strings with `\n`, without a trailing line break.
+pub(crate) fn op_unlines(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'unlines' requires a list of strings"))?;
+    let lines: Vec<&str> = l
+        .iter()
+        .map(|line| {
+            line.get_str()
+                .ok_or_else(|| miette!("'unlines' requires a list of strings, got element {:?}", line))
+        })
+        .try_collect()?;
+    Ok(DataValue::from(lines.join("\n")))
+}
+
+define_op!(OP_PAD_CENTER, 3, false);
+/// Lengths are counted in Unicode scalars; odd padding puts the extra fill on the right.
+pub(crate) fn op_pad_center(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'pad_center' requires a string"))?;
+    let len = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'pad_center' requires a non-negative integer length"))?
+        as usize;
+    let fill = args[2]
+        .get_str()
+        .ok_or_else(|| miette!("'pad_center' requires a string to pad with"))?;
+    let mut fill_chars = fill.chars();
+    let fill = match (fill_chars.next(), fill_chars.next()) {
+        (Some(c), None) => c,
+        _ => bail!("'pad_center' requires a single character to pad with"),
+    };
+    let current = s.chars().count();
+    if current >= len {
+        return Ok(args[0].clone());
+    }
+    let left = (len - current) / 2;
+    let right = len - current - left;
+    let mut ret = String::with_capacity(s.len() + (left + right) * fill.len_utf8());
+    ret.extend(std::iter::repeat_n(fill, left));
+    ret.push_str(s);
+    ret.extend(std::iter::repeat_n(fill, right));
+    Ok(DataValue::from(ret))
+}
+
+define_op!(OP_STR_REVERSE_GRAPHEMES, 1, false);
+/// Reverses by extended grapheme clusters, so combining marks and emoji
+/// sequences stay attached to their base characters.
+pub(crate) fn op_str_reverse_graphemes(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'str_reverse_graphemes' requires a string"))?;
+    Ok(DataValue::from(s.graphemes(true).rev().collect::<String>()))
+}
+
+define_op!(OP_UNICODE_NORMALIZE, 2, false);
+/// Normalizes a string to one of the Unicode normal forms `NFC`, `NFD`,
+/// `NFKC` or `NFKD`.
+pub(crate) fn op_unicode_normalize(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'unicode_normalize' requires a string"))?;
+    let form = args[1]
+        .get_str()
+        .ok_or_else(|| miette!("'unicode_normalize' requires a string for the form"))?;
+    Ok(DataValue::from(match form {
+        "NFC" => s.nfc().collect::<String>(),
+        "NFD" => s.nfd().collect(),
+        "NFKC" => s.nfkc().collect(),
+        "NFKD" => s.nfkd().collect(),
+        _ => bail!("'unicode_normalize' got unknown normal form '{}'", form),
+    }))
+}
+
+define_op!(OP_CASEFOLD, 1, false);
+/// Applies full Unicode case folding, for caseless comparison. Unlike
+/// `lowercase`, this may change the length of the string (e.g. `ß` folds to `ss`).
+pub(crate) fn op_casefold(args: &[DataValue]) -> Result<DataValue> {
+    let s = args[0]
+        .get_str()
+        .ok_or_else(|| miette!("'casefold' requires a string"))?;
+    Ok(DataValue::from(caseless::default_case_fold_str(s)))
+}
+
 
 
 define_op!(OP_INT_RANGE, 1, true);
@@ -552,6 +2049,13 @@ pub(crate) fn op_now(_args: &[DataValue]) -> Result<DataValue> {
     ))
 }
 
+define_op!(OP_NOW_MICROS, 0, false);
+/// Microseconds since the epoch, at the same resolution as [current_validity].
+pub(crate) fn op_now_micros(_args: &[DataValue]) -> Result<DataValue> {
+    let ValidityTs(Reverse(ts_micros)) = current_validity();
+    Ok(DataValue::from(ts_micros))
+}
+
 pub(crate) fn current_validity() -> ValidityTs {
     #[cfg(not(target_arch = "wasm32"))]
     let ts_micros = {
@@ -623,6 +2127,30 @@ pub(crate) fn str2vld(s: &str) -> Result<ValidityTs> {
     Ok(ValidityTs(Reverse(microseconds as i64)))
 }
 
+define_op!(OP_VALIDITY, 2, false);
+/// A validity at the given millisecond timestamp, asserting or retracting according to the flag.
+pub(crate) fn op_validity(args: &[DataValue]) -> Result<DataValue> {
+    let millis = args[0]
+        .get_int()
+        .ok_or_else(|| miette!("'validity' requires an integer timestamp in milliseconds"))?;
+    let is_assert = args[1]
+        .get_bool()
+        .ok_or_else(|| miette!("'validity' requires a boolean assert flag"))?;
+    let micros = millis
+        .checked_mul(1000)
+        .ok_or_else(|| miette!("'validity' timestamp {} is out of range", millis))?;
+    Ok(DataValue::Validity(Validity::from((micros, is_assert))))
+}
+
+define_op!(OP_VALIDITY_TO_MILLIS, 1, false);
+/// The timestamp of a validity, in milliseconds since the epoch.
+pub(crate) fn op_validity_to_millis(args: &[DataValue]) -> Result<DataValue> {
+    match &args[0] {
+        DataValue::Validity(vld) => Ok(DataValue::from(vld.timestamp.0 .0 / 1000)),
+        v => bail!("'validity_to_millis' requires a validity, got {:?}", v),
+    }
+}
+
 // define_op!(OP_RAND_UUID_V1, 0, false);
 // pub(crate) fn op_rand_uuid_v1(_args: &[DataValue]) -> Result<DataValue> {
 //     let mut rng = rand::thread_rng();
@@ -646,6 +2174,74 @@ pub(crate) fn str2vld(s: &str) -> Result<ValidityTs> {
 //     Ok(DataValue::uuid(id))
 // }
 
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Reseeds the generator behind `rand_float`, `rand_int`, `sample` and `shuffle` on the
+/// current thread, so that their results become reproducible. `None` reseeds from entropy.
+pub fn set_rand_seed(seed: Option<u64>) {
+    let rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    RNG.with(|r| *r.borrow_mut() = rng);
+}
+
+pub(crate) fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|r| f(&mut r.borrow_mut()))
+}
+
+define_op!(OP_RAND_FLOAT, 0, false);
+pub(crate) fn op_rand_float(_args: &[DataValue]) -> Result<DataValue> {
+    Ok(DataValue::from(with_rng(|rng| rng.gen::<f64>())))
+}
+
+define_op!(OP_RAND_INT, 2, false);
+/// Returns an integer in `[low, high)`.
+pub(crate) fn op_rand_int(args: &[DataValue]) -> Result<DataValue> {
+    let low = args[0]
+        .get_int()
+        .ok_or_else(|| miette!("'rand_int' requires integer bounds"))?;
+    let high = args[1]
+        .get_int()
+        .ok_or_else(|| miette!("'rand_int' requires integer bounds"))?;
+    ensure!(
+        low < high,
+        "'rand_int' requires the lower bound to be less than the upper bound, got {} and {}",
+        low,
+        high
+    );
+    Ok(DataValue::from(with_rng(|rng| rng.gen_range(low..high))))
+}
+
+define_op!(OP_SAMPLE, 2, false);
+/// Picks `n` distinct elements of a list at random, in random order. If `n` is at least
+/// the length of the list, the whole list is returned shuffled.
+pub(crate) fn op_sample(args: &[DataValue]) -> Result<DataValue> {
+    let l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'sample' requires a list"))?;
+    let n = args[1]
+        .get_non_neg_int()
+        .ok_or_else(|| miette!("'sample' requires a non-negative integer count"))?;
+    let n = (n as usize).min(l.len());
+    let mut l = l.to_vec();
+    let picked = with_rng(|rng| l.partial_shuffle(rng, n).0.to_vec());
+    Ok(DataValue::List(picked))
+}
+
+define_op!(OP_SHUFFLE, 1, false);
+/// A randomly permuted copy of a list.
+pub(crate) fn op_shuffle(args: &[DataValue]) -> Result<DataValue> {
+    let mut l = args[0]
+        .get_slice()
+        .ok_or_else(|| miette!("'shuffle' requires a list"))?
+        .to_vec();
+    with_rng(|rng| l.shuffle(rng));
+    Ok(DataValue::List(l))
+}
+
 define_op!(OP_RAND_UUID_V4, 0, false);
 pub(crate) fn op_rand_uuid_v4(_args: &[DataValue]) -> Result<DataValue> {
     let id = uuid::Uuid::new_v4();
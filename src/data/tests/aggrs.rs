@@ -233,6 +233,30 @@ fn test_collect() {
     );
 }
 
+#[test]
+fn test_group_concat() {
+    let mut aggr = parse_aggr("group_concat").unwrap().clone();
+    aggr.normal_init(&[]).unwrap();
+
+    let mut concat_aggr = aggr.normal_op.unwrap();
+    assert_eq!(concat_aggr.get().unwrap(), DataValue::from(""));
+    concat_aggr.set(&DataValue::from("a")).unwrap();
+    concat_aggr.set(&DataValue::from("b")).unwrap();
+    concat_aggr.set(&DataValue::from("a")).unwrap();
+    assert_eq!(concat_aggr.get().unwrap(), DataValue::from("a,b,a"));
+    assert!(concat_aggr.set(&DataValue::from(1)).is_err());
+
+    let mut aggr = parse_aggr("group_concat").unwrap().clone();
+    aggr.normal_init(&[DataValue::from(" | ")]).unwrap();
+    let mut concat_aggr = aggr.normal_op.unwrap();
+    concat_aggr.set(&DataValue::from("x")).unwrap();
+    concat_aggr.set(&DataValue::from("y")).unwrap();
+    assert_eq!(concat_aggr.get().unwrap(), DataValue::from("x | y"));
+
+    let mut aggr = parse_aggr("group_concat").unwrap().clone();
+    assert!(aggr.normal_init(&[DataValue::from(1)]).is_err());
+}
+
 #[test]
 fn test_count() {
     let mut aggr = parse_aggr("count").unwrap().clone();
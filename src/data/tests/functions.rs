@@ -9,10 +9,12 @@
 // use approx::AbsDiffEq;
 // use num_traits::FloatConst;
 // use regex::Regex;
-// use serde_json::json;
+use std::collections::BTreeSet;
+
+use serde_json::json;
 
 use crate::data::functions::*;
-use crate::data::value::{DataValue};
+use crate::data::value::{DataValue, JsonData, Validity};
 // use crate::DbInstance;
 
 #[test]
@@ -90,6 +92,85 @@ fn test_eq_neq() {
     );
 }
 
+#[test]
+fn test_nullif() {
+    let nullif = |a: DataValue, b: DataValue| op_nullif(&[a, b]).unwrap();
+    assert_eq!(nullif(DataValue::from(1), DataValue::from(1.0)), DataValue::Null);
+    assert_eq!(nullif(DataValue::from("a"), DataValue::from("a")), DataValue::Null);
+    assert_eq!(nullif(DataValue::from(1), DataValue::from(2)), DataValue::from(1));
+    assert_eq!(nullif(DataValue::from(1.5), DataValue::from(1)), DataValue::from(1.5));
+    assert_eq!(nullif(DataValue::from("1"), DataValue::from(1)), DataValue::from("1"));
+    assert_eq!(nullif(DataValue::Null, DataValue::Null), DataValue::Null);
+    assert_eq!(nullif(DataValue::Null, DataValue::from(1)), DataValue::Null);
+    assert_eq!(nullif(DataValue::from(1), DataValue::Null), DataValue::from(1));
+}
+
+#[test]
+fn test_deep_equal() {
+    assert_eq!(
+        op_eq(&[
+            DataValue::List(vec![DataValue::from(1)]),
+            DataValue::List(vec![DataValue::from(1.0)])
+        ])
+        .unwrap(),
+        DataValue::from(false)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::List(vec![DataValue::from(1)]),
+            DataValue::List(vec![DataValue::from(1.0)])
+        ])
+        .unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::List(vec![
+                DataValue::from("a"),
+                DataValue::List(vec![DataValue::from(2), DataValue::from(3.0)])
+            ]),
+            DataValue::List(vec![
+                DataValue::from("a"),
+                DataValue::List(vec![DataValue::from(2.0), DataValue::from(3)])
+            ])
+        ])
+        .unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::List(vec![DataValue::from(1), DataValue::from(2)]),
+            DataValue::List(vec![DataValue::from(1.0)])
+        ])
+        .unwrap(),
+        DataValue::from(false)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::List(vec![DataValue::List(vec![DataValue::from(1)])]),
+            DataValue::List(vec![DataValue::List(vec![DataValue::from(1.5)])])
+        ])
+        .unwrap(),
+        DataValue::from(false)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::Json(JsonData(json!({"a": [1, {"b": 2}]}))),
+            DataValue::Json(JsonData(json!({"a": [1.0, {"b": 2.0}]})))
+        ])
+        .unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(
+        op_deep_equal(&[
+            DataValue::Json(JsonData(json!({"a": 1}))),
+            DataValue::Json(JsonData(json!({"a": 1, "b": 2})))
+        ])
+        .unwrap(),
+        DataValue::from(false)
+    );
+}
+
 #[test]
 fn test_list() {
     assert_eq!(op_list(&[]).unwrap(), DataValue::List(vec![]));
@@ -103,6 +184,412 @@ fn test_list() {
     );
 }
 
+#[test]
+fn test_dedup_collect() {
+    let set = |xs: &[DataValue]| DataValue::Set(xs.iter().cloned().collect());
+    assert_eq!(op_dedup_collect(&[]).unwrap(), set(&[]));
+    assert_eq!(
+        op_dedup_collect(&[DataValue::from(2), DataValue::from("a"), DataValue::from(1)]).unwrap(),
+        set(&[DataValue::from(1), DataValue::from(2), DataValue::from("a")])
+    );
+    let collected = op_dedup_collect(&[
+        DataValue::from("a"),
+        DataValue::from(1),
+        DataValue::from("a"),
+        DataValue::from(1),
+    ])
+    .unwrap();
+    assert_eq!(collected, set(&[DataValue::from(1), DataValue::from("a")]));
+    match collected {
+        DataValue::Set(s) => assert_eq!(s.len(), 2),
+        v => panic!("expected a set, got {v:?}"),
+    }
+}
+
+#[test]
+fn test_assoc_get() {
+    let pair = |k: &str, v: i64| DataValue::List(vec![DataValue::from(k), DataValue::from(v)]);
+    let assoc = DataValue::List(vec![pair("a", 1), pair("b", 2), pair("a", 3)]);
+    assert_eq!(
+        op_assoc_get(&[assoc.clone(), DataValue::from("a")]).unwrap(),
+        DataValue::from(1)
+    );
+    assert_eq!(
+        op_assoc_get(&[assoc.clone(), DataValue::from("b")]).unwrap(),
+        DataValue::from(2)
+    );
+    assert_eq!(
+        op_assoc_get(&[assoc.clone(), DataValue::from("z")]).unwrap(),
+        DataValue::Null
+    );
+    assert_eq!(
+        op_assoc_get(&[assoc.clone(), DataValue::from("z"), DataValue::from(0)]).unwrap(),
+        DataValue::from(0)
+    );
+    assert_eq!(
+        op_assoc_get(&[assoc, DataValue::from("b"), DataValue::from(0)]).unwrap(),
+        DataValue::from(2)
+    );
+    assert_eq!(
+        op_assoc_get(&[DataValue::List(vec![]), DataValue::from("a")]).unwrap(),
+        DataValue::Null
+    );
+
+    let malformed = DataValue::List(vec![
+        pair("a", 1),
+        DataValue::List(vec![DataValue::from("b")]),
+    ]);
+    assert!(op_assoc_get(&[malformed, DataValue::from("a")]).is_err());
+    assert!(op_assoc_get(&[
+        DataValue::List(vec![DataValue::from("a")]),
+        DataValue::from("a")
+    ])
+    .is_err());
+    assert!(op_assoc_get(&[DataValue::from("a"), DataValue::from("a")]).is_err());
+}
+
+#[test]
+fn test_slice_step() {
+    let l = DataValue::List((0..6).map(DataValue::from).collect());
+    let slice = |start: i64, end: i64, stride: i64| {
+        op_slice_step(&[
+            l.clone(),
+            DataValue::from(start),
+            DataValue::from(end),
+            DataValue::from(stride),
+        ])
+    };
+    let ints = |xs: &[i64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    assert_eq!(slice(1, 4, 1).unwrap(), ints(&[1, 2, 3]));
+    assert_eq!(slice(0, 6, 2).unwrap(), ints(&[0, 2, 4]));
+    assert_eq!(slice(1, 6, 2).unwrap(), ints(&[1, 3, 5]));
+    assert_eq!(slice(0, -1, 3).unwrap(), ints(&[0, 3]));
+    assert_eq!(slice(-3, 6, 2).unwrap(), ints(&[3, 5]));
+    assert_eq!(slice(4, 2, 1).unwrap(), ints(&[]));
+    assert_eq!(slice(0, 6, 10).unwrap(), ints(&[0]));
+    assert!(slice(0, 6, 0).is_err());
+    assert!(slice(0, 6, -1).is_err());
+    assert!(slice(0, 7, 1).is_err());
+}
+
+#[test]
+fn test_list_set() {
+    let l = DataValue::List((0..3).map(DataValue::from).collect());
+    let set = |idx: i64| op_list_set(&[l.clone(), DataValue::from(idx), DataValue::from("x")]);
+    let list = |xs: Vec<DataValue>| DataValue::List(xs);
+    let (a, b, c, x) = (
+        DataValue::from(0),
+        DataValue::from(1),
+        DataValue::from(2),
+        DataValue::from("x"),
+    );
+    assert_eq!(set(0).unwrap(), list(vec![x.clone(), b.clone(), c.clone()]));
+    assert_eq!(set(1).unwrap(), list(vec![a.clone(), x.clone(), c.clone()]));
+    assert_eq!(set(-1).unwrap(), list(vec![a.clone(), b.clone(), x.clone()]));
+    assert_eq!(l, list(vec![a, b, c]));
+    assert!(set(3).is_err());
+    assert!(set(-4).is_err());
+    assert!(op_list_set(&[DataValue::from(1), DataValue::from(0), DataValue::Null]).is_err());
+}
+
+#[test]
+fn test_list_insert_remove() {
+    let l = DataValue::List((0..3).map(DataValue::from).collect());
+    let ints = |xs: &[i64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    let insert = |idx: i64| op_list_insert(&[l.clone(), DataValue::from(idx), DataValue::from(9)]);
+    let remove = |idx: i64| op_list_remove(&[l.clone(), DataValue::from(idx)]);
+
+    assert_eq!(insert(3).unwrap(), ints(&[0, 1, 2, 9]));
+    assert_eq!(insert(0).unwrap(), ints(&[9, 0, 1, 2]));
+    assert_eq!(insert(-1).unwrap(), ints(&[0, 1, 9, 2]));
+    assert!(insert(4).is_err());
+    assert!(insert(-4).is_err());
+
+    assert_eq!(remove(0).unwrap(), ints(&[1, 2]));
+    assert_eq!(remove(-1).unwrap(), ints(&[0, 1]));
+    assert!(remove(3).is_err());
+    assert!(remove(-4).is_err());
+    assert!(op_list_remove(&[DataValue::List(vec![]), DataValue::from(0)]).is_err());
+
+    assert_eq!(l, ints(&[0, 1, 2]));
+}
+
+#[test]
+fn test_field() {
+    let row = DataValue::List(vec![DataValue::from(1), DataValue::from("a"), DataValue::Null]);
+    let field = |idx: i64| op_field(&[row.clone(), DataValue::from(idx)]);
+    assert_eq!(field(0).unwrap(), DataValue::from(1));
+    assert_eq!(field(1).unwrap(), DataValue::from("a"));
+    assert_eq!(field(2).unwrap(), DataValue::Null);
+    let err = field(3).unwrap_err();
+    assert!(err.to_string().contains("arity 3"));
+    assert!(field(-1).is_err());
+    assert!(op_field(&[DataValue::from("abc"), DataValue::from(0)]).is_err());
+}
+
+#[test]
+fn test_fill() {
+    let fill = |v: DataValue, n: i64| op_fill(&[v, DataValue::from(n)]);
+    assert_eq!(fill(DataValue::from(1), 0).unwrap(), DataValue::List(vec![]));
+    assert_eq!(
+        fill(DataValue::from("x"), 3).unwrap(),
+        DataValue::List(vec![DataValue::from("x"); 3])
+    );
+    let row = DataValue::List(vec![DataValue::from(0), DataValue::Null]);
+    assert_eq!(
+        fill(row.clone(), 2).unwrap(),
+        DataValue::List(vec![row.clone(), row])
+    );
+    assert!(fill(DataValue::from(1), -1).is_err());
+    assert!(fill(DataValue::from(1), 100_000_000_000_000).is_err());
+    assert!(fill(DataValue::Null, (1 << 20) + 1).is_err());
+    assert!(op_fill(&[DataValue::from(1), DataValue::from(1.5)]).is_err());
+}
+
+#[test]
+fn test_prefix_sum() {
+    let sums = |xs: Vec<DataValue>| op_prefix_sum(&[DataValue::List(xs)]);
+    assert_eq!(sums(vec![]).unwrap(), DataValue::List(vec![]));
+    assert_eq!(
+        sums(vec![1.into(), 2.into(), 3.into()]).unwrap(),
+        DataValue::List(vec![1.into(), 3.into(), 6.into()])
+    );
+    assert_eq!(
+        sums(vec![1.into(), 0.5.into(), 2.into()]).unwrap(),
+        DataValue::List(vec![1.into(), 1.5.into(), 3.5.into()])
+    );
+    assert!(sums(vec![1.into(), "2".into()]).is_err());
+    assert!(op_prefix_sum(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_diff() {
+    let diff = |xs: Vec<DataValue>| op_diff(&[DataValue::List(xs)]);
+    assert_eq!(
+        diff(vec![1.into(), 3.into(), 6.into(), 10.into()]).unwrap(),
+        DataValue::List(vec![2.into(), 3.into(), 4.into()])
+    );
+    assert_eq!(
+        diff(vec![1.into(), 1.5.into()]).unwrap(),
+        DataValue::List(vec![0.5.into()])
+    );
+    assert_eq!(diff(vec![7.into()]).unwrap(), DataValue::List(vec![]));
+    assert_eq!(diff(vec![]).unwrap(), DataValue::List(vec![]));
+    let xs = vec![1.into(), 2.into(), 4.into()];
+    let sums = op_prefix_sum(&[DataValue::List(xs.clone())]).unwrap();
+    assert_eq!(
+        op_diff(&[sums]).unwrap(),
+        DataValue::List(xs[1..].to_vec())
+    );
+    assert!(diff(vec!["a".into()]).is_err());
+    assert!(diff(vec![1.into(), DataValue::Null]).is_err());
+}
+
+#[test]
+fn test_clamp_list() {
+    let clamp = |xs: Vec<DataValue>, low: DataValue, high: DataValue| {
+        op_clamp_list(&[DataValue::List(xs), low, high])
+    };
+    assert_eq!(
+        clamp(vec![(-5).into(), 3.into(), 2.5.into(), 42.into()], 0.into(), 10.into()).unwrap(),
+        DataValue::List(vec![0.into(), 3.into(), 2.5.into(), 10.into()])
+    );
+    assert_eq!(
+        clamp(vec![1.into(), 0.into()], 1.0.into(), 1.5.into()).unwrap(),
+        DataValue::List(vec![1.into(), 1.0.into()])
+    );
+    assert_eq!(
+        clamp(vec![], 0.into(), 0.into()).unwrap(),
+        DataValue::List(vec![])
+    );
+    assert!(clamp(vec![1.into()], 2.into(), 1.into()).is_err());
+    assert!(clamp(vec!["a".into()], 0.into(), 1.into()).is_err());
+    assert!(clamp(vec![1.into()], DataValue::Null, 1.into()).is_err());
+}
+
+#[test]
+fn test_cosine_similarity() {
+    let list = |xs: &[f64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    let cos = |a: &[f64], b: &[f64]| op_cosine_similarity(&[list(a), list(b)]);
+    assert_eq!(cos(&[1., 2., 3.], &[1., 2., 3.]).unwrap(), DataValue::from(1.0));
+    assert_eq!(cos(&[1., 2., 3.], &[2., 4., 6.]).unwrap(), DataValue::from(1.0));
+    assert_eq!(cos(&[1., 0.], &[0., 5.]).unwrap(), DataValue::from(0.0));
+    assert_eq!(cos(&[1., 1.], &[-1., -1.]).unwrap(), DataValue::from(-1.0));
+    assert_eq!(cos(&[0., 0.], &[1., 2.]).unwrap(), DataValue::Null);
+    assert_eq!(cos(&[], &[]).unwrap(), DataValue::Null);
+    let mixed = DataValue::List(vec![DataValue::from(3), DataValue::from(4.0)]);
+    assert_eq!(
+        op_cosine_similarity(&[mixed.clone(), mixed]).unwrap(),
+        DataValue::from(1.0)
+    );
+    assert!(cos(&[1., 2.], &[1.]).is_err());
+    assert!(op_cosine_similarity(&[list(&[1.]), DataValue::List(vec!["a".into()])]).is_err());
+}
+
+#[test]
+fn test_replace_null() {
+    let l = DataValue::List(vec![
+        DataValue::from(1),
+        DataValue::Null,
+        DataValue::List(vec![DataValue::Null, DataValue::from(2)]),
+    ]);
+    let zero = DataValue::from(0);
+    assert_eq!(
+        op_replace_null(&[l.clone(), zero.clone()]).unwrap(),
+        DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from(0),
+            DataValue::List(vec![DataValue::Null, DataValue::from(2)]),
+        ])
+    );
+    assert_eq!(
+        op_replace_null(&[l.clone(), zero.clone(), DataValue::Bool(false)]).unwrap(),
+        op_replace_null(&[l.clone(), zero.clone()]).unwrap()
+    );
+    assert_eq!(
+        op_replace_null(&[l.clone(), zero.clone(), DataValue::Bool(true)]).unwrap(),
+        DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from(0),
+            DataValue::List(vec![DataValue::from(0), DataValue::from(2)]),
+        ])
+    );
+    assert!(op_replace_null(&[l.clone(), zero.clone(), DataValue::from(1)]).is_err());
+    assert!(op_replace_null(&[DataValue::Null, zero]).is_err());
+}
+
+#[test]
+fn test_transpose() {
+    let ints = |xs: &[i64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    let matrix = DataValue::List(vec![ints(&[1, 2, 3]), ints(&[4, 5, 6])]);
+    assert_eq!(
+        op_transpose(&[matrix]).unwrap(),
+        DataValue::List(vec![ints(&[1, 4]), ints(&[2, 5]), ints(&[3, 6])])
+    );
+    assert_eq!(op_transpose(&[ints(&[])]).unwrap(), ints(&[]));
+    assert_eq!(
+        op_transpose(&[DataValue::List(vec![ints(&[]), ints(&[])])]).unwrap(),
+        ints(&[])
+    );
+
+    let ragged = DataValue::List(vec![ints(&[1, 2]), ints(&[3, 4]), ints(&[5])]);
+    let err = op_transpose(&[ragged]).unwrap_err();
+    assert!(err.to_string().contains("row 2"));
+    assert!(op_transpose(&[DataValue::List(vec![DataValue::from(1)])]).is_err());
+}
+
+#[test]
+fn test_hash() {
+    let hash = |v: DataValue| op_hash(&[v]).unwrap();
+    assert_eq!(hash(DataValue::Null), DataValue::from(-5808590958014384161i64));
+    assert_eq!(hash(DataValue::from("a")), DataValue::from(8346312916548139945i64));
+    assert_eq!(hash(DataValue::from(1)), hash(DataValue::from(1.0)));
+    assert_ne!(hash(DataValue::from(1)), hash(DataValue::from(1.5)));
+    assert_ne!(hash(DataValue::from(1)), hash(DataValue::from("1")));
+    assert_ne!(hash(DataValue::from("a")), hash(DataValue::from("b")));
+    let l = || DataValue::List(vec![DataValue::from(1), DataValue::from("x")]);
+    assert_eq!(hash(l()), hash(l()));
+    assert_eq!(
+        hash(l()),
+        hash(DataValue::List(vec![DataValue::from(1.0), DataValue::from("x")]))
+    );
+    assert_ne!(
+        hash(DataValue::List(vec![DataValue::from("ab"), DataValue::from("c")])),
+        hash(DataValue::List(vec![DataValue::from("a"), DataValue::from("bc")]))
+    );
+    let set = DataValue::Set(l().get_slice().unwrap().iter().cloned().collect());
+    assert_ne!(hash(l()), hash(set));
+    let j = |s: &str| DataValue::Json(JsonData(serde_json::from_str(s).unwrap()));
+    assert_eq!(hash(j(r#"{"a": 1, "b": [2]}"#)), hash(j(r#"{"b": [2.0], "a": 1}"#)));
+    assert_ne!(hash(j(r#"{"a": 1}"#)), hash(j(r#"{"a": 2}"#)));
+}
+
+#[test]
+fn test_sha256() {
+    assert_eq!(
+        op_sha256(&[DataValue::from("")]).unwrap(),
+        DataValue::from("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    );
+    assert_eq!(
+        op_sha256(&[DataValue::from("abc")]).unwrap(),
+        DataValue::from("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+    assert_eq!(
+        op_sha256(&[DataValue::Bytes(b"abc".to_vec())]).unwrap(),
+        op_sha256(&[DataValue::from("abc")]).unwrap()
+    );
+    assert!(op_sha256(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(op_crc32(&[DataValue::from("")]).unwrap(), DataValue::from(0));
+    assert_eq!(
+        op_crc32(&[DataValue::from("123456789")]).unwrap(),
+        DataValue::from(0xcbf43926i64)
+    );
+    assert_eq!(
+        op_crc32(&[DataValue::Bytes(b"abc".to_vec())]).unwrap(),
+        DataValue::from(0x352441c2i64)
+    );
+    assert!(op_crc32(&[DataValue::Null]).is_err());
+}
+
+#[test]
+fn test_uniq_count() {
+    let count = |xs: Vec<DataValue>| op_uniq_count(&[DataValue::List(xs)]).unwrap();
+    assert_eq!(count(vec![]), DataValue::from(0));
+    assert_eq!(count(vec![DataValue::from("a"); 4]), DataValue::from(1));
+    assert_eq!(
+        count(vec![DataValue::from(1), DataValue::from("1"), DataValue::Null]),
+        DataValue::from(3)
+    );
+    assert_eq!(
+        count(vec![DataValue::from(1), DataValue::from(2), DataValue::from(1)]),
+        DataValue::from(2)
+    );
+    assert!(op_uniq_count(&[DataValue::from("abc")]).is_err());
+}
+
+#[test]
+fn test_flatten() {
+    let nested = DataValue::List(vec![
+        DataValue::List(vec![DataValue::from(1), DataValue::from(2)]),
+        DataValue::from(3),
+        DataValue::List(vec![
+            DataValue::List(vec![DataValue::from(4)]),
+            DataValue::from(5),
+        ]),
+        DataValue::List(vec![]),
+    ]);
+    assert_eq!(
+        op_flatten(std::slice::from_ref(&nested)).unwrap(),
+        DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from(2),
+            DataValue::from(3),
+            DataValue::List(vec![DataValue::from(4)]),
+            DataValue::from(5),
+        ])
+    );
+    assert_eq!(
+        op_flatten(&[nested.clone(), DataValue::from(2)]).unwrap(),
+        DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from(2),
+            DataValue::from(3),
+            DataValue::from(4),
+            DataValue::from(5),
+        ])
+    );
+    assert_eq!(
+        op_flatten(&[nested.clone(), DataValue::from(0)]).unwrap(),
+        nested
+    );
+    assert!(op_flatten(&[DataValue::from(1)]).is_err());
+    assert!(op_flatten(&[nested, DataValue::from(-1)]).is_err());
+}
+
 #[test]
 fn test_comparators() {
     assert_eq!(
@@ -208,6 +695,38 @@ fn test_comparators() {
     assert!(op_lt(&[DataValue::Null, DataValue::from(true)]).is_err());
 }
 
+#[test]
+fn test_compare_uuid_validity() {
+    let u1 = op_to_uuid(&[DataValue::from("0be7a9a2-52a1-11e7-802a-010203040506")]).unwrap();
+    let u2 = op_to_uuid(&[DataValue::from("f3b4958c-52a1-11e7-802a-010203040506")]).unwrap();
+    assert_eq!(
+        op_gt(&[u2.clone(), u1.clone()]).unwrap(),
+        DataValue::from(u2 > u1)
+    );
+    assert_eq!(
+        op_le(&[u1.clone(), u1.clone()]).unwrap(),
+        DataValue::from(true)
+    );
+    assert!(op_gt(&[u1.clone(), DataValue::from(1)]).is_err());
+
+    // validities sort descendingly by timestamp, following `DataValue`'s order
+    let earlier = DataValue::Validity(Validity::from((10, true)));
+    let later = DataValue::Validity(Validity::from((20, true)));
+    assert_eq!(
+        op_gt(&[earlier.clone(), later.clone()]).unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(
+        op_le(&[earlier.clone(), later.clone()]).unwrap(),
+        DataValue::from(false)
+    );
+    assert_eq!(
+        op_le(&[later.clone(), later]).unwrap(),
+        DataValue::from(true)
+    );
+    assert!(op_le(&[earlier, u1]).is_err());
+}
+
 #[test]
 fn test_max_min() {
     assert_eq!(op_max(&[DataValue::from(1),]).unwrap(), DataValue::from(1));
@@ -277,6 +796,107 @@ fn test_max_min() {
     assert!(op_max(&[DataValue::from(true)]).is_err());
 }
 
+#[test]
+fn test_greatest_least() {
+    let strs = ["pear", "apple", "zucchini"].map(DataValue::from);
+    assert_eq!(op_greatest(&strs).unwrap(), DataValue::from("zucchini"));
+    assert_eq!(op_least(&strs).unwrap(), DataValue::from("apple"));
+
+    let nums = [
+        DataValue::from(3),
+        DataValue::Null,
+        DataValue::from(7.5),
+        DataValue::from(-1),
+    ];
+    assert_eq!(op_greatest(&nums).unwrap(), DataValue::from(7.5));
+    assert_eq!(op_least(&nums).unwrap(), DataValue::from(-1));
+
+    let nulls = [DataValue::Null, DataValue::Null];
+    assert_eq!(op_greatest(&nulls).unwrap(), DataValue::Null);
+    assert_eq!(op_least(&nulls).unwrap(), DataValue::Null);
+
+    assert!(op_greatest(&[DataValue::from(1), DataValue::from("a")]).is_err());
+}
+
+#[test]
+fn test_max_by_min_by() {
+    let rows = DataValue::List(vec![
+        DataValue::List(vec![DataValue::from("a"), DataValue::from(3)]),
+        DataValue::List(vec![DataValue::from("b"), DataValue::from(7.5)]),
+        DataValue::List(vec![DataValue::from("c"), DataValue::from(-1)]),
+        DataValue::List(vec![DataValue::from("d"), DataValue::from(7.5)]),
+        DataValue::List(vec![DataValue::from("e"), DataValue::from(-1.0)]),
+    ]);
+    assert_eq!(
+        op_max_by(&[rows.clone(), DataValue::from(1)]).unwrap(),
+        DataValue::List(vec![DataValue::from("b"), DataValue::from(7.5)])
+    );
+    assert_eq!(
+        op_min_by(&[rows.clone(), DataValue::from(1)]).unwrap(),
+        DataValue::List(vec![DataValue::from("c"), DataValue::from(-1)])
+    );
+    assert_eq!(
+        op_max_by(&[rows.clone(), DataValue::from(0)]).unwrap(),
+        DataValue::List(vec![DataValue::from("e"), DataValue::from(-1.0)])
+    );
+    assert_eq!(
+        op_max_by(&[DataValue::List(vec![]), DataValue::from(0)]).unwrap(),
+        DataValue::Null
+    );
+    assert!(op_max_by(&[rows.clone(), DataValue::from(2)]).is_err());
+    assert!(op_min_by(&[
+        DataValue::List(vec![DataValue::from(1)]),
+        DataValue::from(0)
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_median_variance_stddev() {
+    let list = |xs: &[f64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+
+    assert_eq!(
+        op_median(&[list(&[3., 1., 2.])]).unwrap(),
+        DataValue::from(2.0)
+    );
+    assert_eq!(
+        op_median(&[list(&[4., 1., 3., 2.])]).unwrap(),
+        DataValue::from(2.5)
+    );
+    assert_eq!(
+        op_median(&[DataValue::List(vec![DataValue::from(7)])]).unwrap(),
+        DataValue::from(7.0)
+    );
+
+    let data = list(&[2., 4., 4., 4., 5., 5., 7., 9.]);
+    assert_eq!(
+        op_variance(std::slice::from_ref(&data)).unwrap(),
+        DataValue::from(4.0)
+    );
+    assert_eq!(op_stddev(&[data]).unwrap(), DataValue::from(2.0));
+    assert_eq!(
+        op_variance(&[DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from(2),
+            DataValue::from(3)
+        ])])
+        .unwrap()
+        .get_float()
+        .unwrap(),
+        2. / 3.
+    );
+    assert_eq!(
+        op_stddev(&[list(&[5.])]).unwrap(),
+        DataValue::from(0.0)
+    );
+
+    for op in [op_median, op_variance, op_stddev] {
+        assert!(op(&[DataValue::List(vec![])]).is_err());
+        assert!(op(&[DataValue::List(vec![DataValue::from(1), DataValue::from("a")])]).is_err());
+        assert!(op(&[DataValue::from(1)]).is_err());
+    }
+}
+
 #[test]
 fn test_minus() {
     assert_eq!(
@@ -307,6 +927,94 @@ fn test_mod() {
     assert!(op_mod(&[DataValue::from(5.), DataValue::from(0.)]).is_ok());
     assert!(op_mod(&[DataValue::from(5.), DataValue::from(0)]).is_ok());
     assert!(op_mod(&[DataValue::from(5), DataValue::from(0)]).is_err());
+    assert!(op_mod(&[DataValue::from(5.), DataValue::from(0.)])
+        .unwrap()
+        .get_float()
+        .unwrap()
+        .is_nan());
+}
+
+#[test]
+fn test_safe_mod() {
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(-10), DataValue::from(7)]).unwrap(),
+        DataValue::from(-3)
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(7.5), DataValue::from(2)]).unwrap(),
+        DataValue::from(1.5)
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(5), DataValue::from(0)]).unwrap(),
+        DataValue::Null
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(5), DataValue::from(0.)]).unwrap(),
+        DataValue::Null
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(5.), DataValue::from(-0.)]).unwrap(),
+        DataValue::Null
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(i64::MIN), DataValue::from(-1)]).unwrap(),
+        DataValue::Null
+    );
+    assert_eq!(
+        op_safe_mod(&[DataValue::from(i64::MIN), DataValue::from(3)]).unwrap(),
+        DataValue::from(-2)
+    );
+    assert!(op_safe_mod(&[DataValue::from("a"), DataValue::from(0)]).is_err());
+    assert!(op_safe_mod(&[DataValue::from(1), DataValue::Null]).is_err());
+}
+
+#[test]
+fn test_gcd_lcm() {
+    let gcd = |a: i64, b: i64| op_gcd(&[DataValue::from(a), DataValue::from(b)]);
+    let lcm = |a: i64, b: i64| op_lcm(&[DataValue::from(a), DataValue::from(b)]);
+    assert_eq!(gcd(12, 18).unwrap(), DataValue::from(6));
+    assert_eq!(gcd(-12, 18).unwrap(), DataValue::from(6));
+    assert_eq!(gcd(-12, -18).unwrap(), DataValue::from(6));
+    assert_eq!(gcd(7, 0).unwrap(), DataValue::from(7));
+    assert_eq!(gcd(0, 0).unwrap(), DataValue::from(0));
+    assert!(gcd(i64::MIN, 0).is_err());
+
+    assert_eq!(lcm(4, 6).unwrap(), DataValue::from(12));
+    assert_eq!(lcm(-4, 6).unwrap(), DataValue::from(12));
+    assert_eq!(lcm(0, 6).unwrap(), DataValue::from(0));
+    assert_eq!(lcm(0, 0).unwrap(), DataValue::from(0));
+    assert_eq!(
+        lcm(i64::MAX, i64::MAX).unwrap(),
+        DataValue::from(i64::MAX)
+    );
+    assert!(lcm(i64::MAX, i64::MAX - 1).is_err());
+
+    assert!(op_gcd(&[DataValue::from(4.), DataValue::from(6)]).is_err());
+    assert!(op_lcm(&[DataValue::from(4), DataValue::from("6")]).is_err());
+}
+
+#[test]
+fn test_factorial_binomial() {
+    let fact = |n: i64| op_factorial(&[DataValue::from(n)]);
+    let binom = |n: i64, k: i64| op_binomial(&[DataValue::from(n), DataValue::from(k)]);
+    assert_eq!(fact(0).unwrap(), DataValue::from(1));
+    assert_eq!(fact(1).unwrap(), DataValue::from(1));
+    assert_eq!(fact(5).unwrap(), DataValue::from(120));
+    assert_eq!(fact(20).unwrap(), DataValue::from(2432902008176640000i64));
+    assert!(fact(21).is_err());
+    assert!(fact(-1).is_err());
+    assert!(op_factorial(&[DataValue::from(5.)]).is_err());
+
+    assert_eq!(binom(5, 2).unwrap(), DataValue::from(10));
+    assert_eq!(binom(5, 0).unwrap(), DataValue::from(1));
+    assert_eq!(binom(5, 5).unwrap(), DataValue::from(1));
+    assert_eq!(binom(3, 5).unwrap(), DataValue::from(0));
+    assert_eq!(binom(0, 0).unwrap(), DataValue::from(1));
+    assert_eq!(binom(62, 31).unwrap(), DataValue::from(465428353255261088i64));
+    assert!(binom(70, 35).is_err());
+    assert!(binom(-5, 2).is_err());
+    assert!(binom(5, -2).is_err());
+    assert!(op_binomial(&[DataValue::from(5), DataValue::from(2.)]).is_err());
 }
 
 #[test]
@@ -328,6 +1036,181 @@ fn test_boolean() {
 }
 
 
+#[test]
+fn test_list_json_round_trip() {
+    let list = DataValue::List(vec![
+        DataValue::from(1),
+        DataValue::from(2.5),
+        DataValue::from("a"),
+        DataValue::Null,
+        DataValue::from(false),
+        DataValue::List(vec![DataValue::from(3), DataValue::List(vec![])]),
+        DataValue::Json(JsonData(json!({"k": [1]}))),
+    ]);
+    let json = op_list_to_json(std::slice::from_ref(&list)).unwrap();
+    assert_eq!(
+        json,
+        DataValue::Json(JsonData(
+            json!([1, 2.5, "a", null, false, [3, []], {"k": [1]}])
+        ))
+    );
+    assert_eq!(op_json_to_list(&[json]).unwrap(), list);
+
+    assert!(op_list_to_json(&[DataValue::from("[1]")]).is_err());
+    assert!(op_json_to_list(&[DataValue::Json(JsonData(json!({"a": 1})))]).is_err());
+    assert!(op_json_to_list(&[DataValue::List(vec![])]).is_err());
+}
+
+#[test]
+fn test_json_path_get() {
+    let doc = DataValue::Json(JsonData(json!({
+        "a": [1, {"b": "deep"}],
+        "x/y": {"~k": true}
+    })));
+    let get = |path: &str| op_json_path_get(&[doc.clone(), DataValue::from(path)]);
+    assert_eq!(get("/a/0").unwrap(), DataValue::from(1));
+    assert_eq!(get("/a/1/b").unwrap(), DataValue::from("deep"));
+    assert_eq!(get("/x~1y/~0k").unwrap(), DataValue::from(true));
+    assert_eq!(get("/a/1").unwrap(), DataValue::Json(JsonData(json!({"b": "deep"}))));
+    assert_eq!(get("").unwrap(), doc);
+    assert_eq!(get("/a/5").unwrap(), DataValue::Null);
+    assert_eq!(get("/missing/b").unwrap(), DataValue::Null);
+    assert!(get("a/0").is_err());
+    assert!(op_json_path_get(&[DataValue::from(1), DataValue::from("/a")]).is_err());
+}
+
+#[test]
+fn test_json_keys_values() {
+    let doc = DataValue::Json(JsonData(json!({"b": [1, 2], "c": "x", "a": 1.5})));
+    assert_eq!(
+        op_json_keys(std::slice::from_ref(&doc)).unwrap(),
+        DataValue::List(vec![
+            DataValue::from("a"),
+            DataValue::from("b"),
+            DataValue::from("c")
+        ])
+    );
+    assert_eq!(
+        op_json_values(&[doc]).unwrap(),
+        DataValue::List(vec![
+            DataValue::from(1.5),
+            DataValue::Json(JsonData(json!([1, 2]))),
+            DataValue::from("x")
+        ])
+    );
+    let empty = DataValue::Json(JsonData(json!({})));
+    assert_eq!(op_json_keys(&[empty]).unwrap(), DataValue::List(vec![]));
+    assert!(op_json_keys(&[DataValue::Json(JsonData(json!([1])))]).is_err());
+    assert!(op_json_values(&[DataValue::from("a")]).is_err());
+}
+
+#[test]
+fn test_json_set() {
+    let doc = DataValue::Json(JsonData(json!({"a": {"b": 1}, "l": [1, 2]})));
+    let set = |path: &str, v: DataValue| op_json_set(&[doc.clone(), DataValue::from(path), v]);
+    assert_eq!(
+        set("/a/c", DataValue::from("x")).unwrap(),
+        DataValue::Json(JsonData(json!({"a": {"b": 1, "c": "x"}, "l": [1, 2]})))
+    );
+    assert_eq!(
+        set("/new/deep/key", DataValue::from(true)).unwrap(),
+        DataValue::Json(JsonData(json!({
+            "a": {"b": 1},
+            "l": [1, 2],
+            "new": {"deep": {"key": true}}
+        })))
+    );
+    assert_eq!(
+        set("/l/0", DataValue::List(vec![DataValue::Null])).unwrap(),
+        DataValue::Json(JsonData(json!({"a": {"b": 1}, "l": [[null], 2]})))
+    );
+    assert_eq!(
+        set("/l/-", DataValue::from(3)).unwrap(),
+        DataValue::Json(JsonData(json!({"a": {"b": 1}, "l": [1, 2, 3]})))
+    );
+    assert_eq!(
+        set("", DataValue::from(0)).unwrap(),
+        DataValue::Json(JsonData(json!(0)))
+    );
+    // the original is untouched
+    assert_eq!(doc, DataValue::Json(JsonData(json!({"a": {"b": 1}, "l": [1, 2]}))));
+
+    assert!(set("/l/x", DataValue::Null).is_err());
+    assert!(set("/l/5", DataValue::Null).is_err());
+    assert!(set("/a/b/c", DataValue::Null).is_err());
+    assert!(set("a", DataValue::Null).is_err());
+    assert!(op_json_set(&[DataValue::from(1), DataValue::from("/a"), DataValue::Null]).is_err());
+}
+
+#[test]
+fn test_json_remove() {
+    let doc = DataValue::Json(JsonData(json!({"a": {"b": 1, "c": 2}, "l": [1, 2, 3]})));
+    let remove = |path: &str| op_json_remove(&[doc.clone(), DataValue::from(path)]);
+    assert_eq!(
+        remove("/a/b").unwrap(),
+        DataValue::Json(JsonData(json!({"a": {"c": 2}, "l": [1, 2, 3]})))
+    );
+    assert_eq!(
+        remove("/l/0").unwrap(),
+        DataValue::Json(JsonData(json!({"a": {"b": 1, "c": 2}, "l": [2, 3]})))
+    );
+    assert_eq!(remove("/missing").unwrap(), doc);
+    assert_eq!(remove("/missing/deeper").unwrap(), doc);
+    assert_eq!(remove("/l/7").unwrap(), doc);
+    assert_eq!(remove("/a/b/c").unwrap(), doc);
+    assert!(remove("").is_err());
+    assert!(remove("a").is_err());
+    assert!(op_json_remove(&[DataValue::from(1), DataValue::from("/a")]).is_err());
+}
+
+#[test]
+fn test_json_deep_copy() {
+    let original = DataValue::Json(JsonData(json!({"a": {"b": [1, 2]}})));
+    let mut copy = op_json_deep_copy(std::slice::from_ref(&original)).unwrap();
+    assert_eq!(copy, original);
+    if let DataValue::Json(JsonData(json)) = &mut copy {
+        json["a"]["b"][0] = json!(100);
+    }
+    assert_eq!(original, DataValue::Json(JsonData(json!({"a": {"b": [1, 2]}}))));
+    assert_ne!(copy, original);
+    let list = DataValue::List(vec![DataValue::from(1)]);
+    assert_eq!(op_json_deep_copy(std::slice::from_ref(&list)).unwrap(), list);
+
+    // mutating a merge of the copy leaves the original alone
+    let copy = match op_json_deep_copy(std::slice::from_ref(&original)).unwrap() {
+        DataValue::Json(JsonData(json)) => json,
+        v => panic!("expected json, got {v:?}"),
+    };
+    let mut merged = deep_merge_json(copy, json!({"a": {"c": true}}));
+    assert_eq!(merged, json!({"a": {"b": [1, 2], "c": true}}));
+    merged["a"]["b"][1] = json!(200);
+    merged["a"]["c"] = json!(false);
+    assert_eq!(original, DataValue::Json(JsonData(json!({"a": {"b": [1, 2]}}))));
+}
+
+#[test]
+fn test_pretty_json() {
+    let doc = json!({"a": [1, {"b": null}], "c": "d"});
+    let pretty = op_pretty_json(&[DataValue::Json(JsonData(doc.clone()))]).unwrap();
+    let text = pretty.get_str().unwrap();
+    assert!(text.contains('\n'));
+    assert!(text.contains("\n  \"a\": ["));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(text).unwrap(), doc);
+
+    let wide = op_pretty_json(&[DataValue::Json(JsonData(doc.clone())), DataValue::from(4)])
+        .unwrap();
+    let text = wide.get_str().unwrap();
+    assert!(text.contains("\n    \"a\": ["));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(text).unwrap(), doc);
+
+    let list = DataValue::List(vec![DataValue::from(1), DataValue::from("x")]);
+    assert_eq!(
+        op_pretty_json(&[list]).unwrap(),
+        DataValue::from("[\n  1,\n  \"x\"\n]")
+    );
+    assert!(op_pretty_json(&[DataValue::Null, DataValue::from(-1)]).is_err());
+}
+
 #[test]
 fn test_to_string() {
     assert_eq!(
@@ -337,6 +1220,534 @@ fn test_to_string() {
 }
 
 
+#[test]
+fn test_type_predicates() {
+    assert_eq!(op_is_null(&[DataValue::Null]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_null(&[DataValue::from(0)]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_num(&[DataValue::from(1)]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_num(&[DataValue::from(1.5)]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_num(&[DataValue::from("1")]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_int(&[DataValue::from(1)]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_int(&[DataValue::from(1.0)]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_float(&[DataValue::from(1.0)]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_float(&[DataValue::from(1)]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_string(&[DataValue::from("a")]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_string(&[DataValue::Null]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_list(&[DataValue::List(vec![])]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_list(&[DataValue::from("[]")]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_bytes(&[DataValue::Bytes(vec![1])]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_bytes(&[DataValue::from("a")]).unwrap(), DataValue::from(false));
+    assert_eq!(op_is_bool(&[DataValue::from(false)]).unwrap(), DataValue::from(true));
+    assert_eq!(op_is_bool(&[DataValue::from(0)]).unwrap(), DataValue::from(false));
+    assert_eq!(
+        op_is_json(&[DataValue::Json(JsonData(json!({"a": 1})))]).unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(op_is_json(&[DataValue::from("{}")]).unwrap(), DataValue::from(false));
+}
+
+#[test]
+fn test_float_predicates() {
+    let check = |v: DataValue| {
+        (
+            op_is_finite(std::slice::from_ref(&v)).unwrap(),
+            op_is_infinite(std::slice::from_ref(&v)).unwrap(),
+            op_is_nan(&[v]).unwrap(),
+        )
+    };
+    let (t, f) = (DataValue::from(true), DataValue::from(false));
+    assert_eq!(check(DataValue::from(1.5)), (t.clone(), f.clone(), f.clone()));
+    assert_eq!(check(DataValue::from(i64::MAX)), (t.clone(), f.clone(), f.clone()));
+    assert_eq!(check(DataValue::from(f64::NAN)), (f.clone(), f.clone(), t.clone()));
+    assert_eq!(
+        check(DataValue::from(f64::INFINITY)),
+        (f.clone(), t.clone(), f.clone())
+    );
+    assert_eq!(check(DataValue::from(f64::NEG_INFINITY)), (f.clone(), t, f));
+    assert!(op_is_finite(&[DataValue::from("1")]).is_err());
+    assert!(op_is_infinite(&[DataValue::Null]).is_err());
+    assert!(op_is_nan(&[DataValue::from(true)]).is_err());
+}
+
+#[test]
+fn test_nan_to_null() {
+    for v in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(op_nan_to_null(&[DataValue::from(v)]).unwrap(), DataValue::Null);
+        assert_eq!(
+            op_nan_to_null(&[DataValue::from(v), DataValue::from(0.)]).unwrap(),
+            DataValue::from(0.)
+        );
+    }
+    assert_eq!(
+        op_nan_to_null(&[DataValue::from(1.5)]).unwrap(),
+        DataValue::from(1.5)
+    );
+    assert_eq!(
+        op_nan_to_null(&[DataValue::from(2), DataValue::from(0)]).unwrap(),
+        DataValue::from(2)
+    );
+    assert_eq!(
+        op_nan_to_null(&[DataValue::from("NaN")]).unwrap(),
+        DataValue::from("NaN")
+    );
+    assert_eq!(op_nan_to_null(&[DataValue::Null]).unwrap(), DataValue::Null);
+    assert!(op_nan_to_null(&[
+        DataValue::from(1.),
+        DataValue::from(0.),
+        DataValue::from(0.)
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_parse_float_locale() {
+    let parse = |s: &str, sep: &str| {
+        op_parse_float_locale(&[DataValue::from(s), DataValue::from(sep)])
+    };
+    assert_eq!(parse("1.234,56", ",").unwrap(), DataValue::from(1234.56));
+    assert_eq!(parse("1,234.56", ".").unwrap(), DataValue::from(1234.56));
+    assert_eq!(parse(" -12.345.678,9 ", ",").unwrap(), DataValue::from(-12345678.9));
+    assert_eq!(parse("3,5", ",").unwrap(), DataValue::from(3.5));
+    assert_eq!(parse(",5", ",").unwrap(), DataValue::from(0.5));
+    assert_eq!(parse("1234", ",").unwrap(), DataValue::from(1234.0));
+    // thousands separators must group by three, before the decimal separator
+    assert!(parse("1.23,4", ",").is_err());
+    assert!(parse("1234.567,8", ",").is_err());
+    assert!(parse("1,234,5", ",").is_err());
+    assert!(parse("1,5.000", ",").is_err());
+    assert!(parse("1,2a", ",").is_err());
+    assert!(parse("", ",").is_err());
+    assert!(parse("-", ",").is_err());
+    assert!(parse("1,5", ";").is_err());
+    assert!(op_parse_float_locale(&[DataValue::from(1), DataValue::from(",")]).is_err());
+}
+
+#[test]
+fn test_to_float_to_int() {
+    assert_eq!(op_to_float(&[DataValue::from(2)]).unwrap(), DataValue::from(2.0));
+    assert_eq!(op_to_float(&[DataValue::from("1.5")]).unwrap(), DataValue::from(1.5));
+    assert_eq!(op_to_float(&[DataValue::from("-3")]).unwrap(), DataValue::from(-3.0));
+    assert!(op_to_float(&[DataValue::from("abc")]).is_err());
+    for s in ["PI", "NAN", "INF", "NEG_INF", "inf", "NaN"] {
+        assert!(op_to_float(&[DataValue::from(s)]).is_err());
+    }
+    assert!(op_to_float(&[DataValue::Null]).is_err());
+    assert!(op_to_float(&[DataValue::from(true)]).is_err());
+
+    assert_eq!(op_to_int(&[DataValue::from(2.7)]).unwrap(), DataValue::from(2));
+    assert_eq!(op_to_int(&[DataValue::from(-2.7)]).unwrap(), DataValue::from(-2));
+    assert_eq!(op_to_int(&[DataValue::from(3.0)]).unwrap(), DataValue::from(3));
+    assert_eq!(op_to_int(&[DataValue::from("42")]).unwrap(), DataValue::from(42));
+    assert!(op_to_int(&[DataValue::from("4.2")]).is_err());
+    assert!(op_to_int(&[DataValue::from("forty-two")]).is_err());
+    assert!(op_to_int(&[DataValue::from(f64::NAN)]).is_err());
+    assert!(op_to_int(&[DataValue::from(f64::INFINITY)]).is_err());
+    assert!(op_to_int(&[DataValue::from(1e300)]).is_err());
+    assert!(op_to_int(&[DataValue::from(-1e300)]).is_err());
+    assert!(op_to_int(&[DataValue::from(9223372036854775808.)]).is_err());
+    assert_eq!(
+        op_to_int(&[DataValue::from(-9223372036854775808.)]).unwrap(),
+        DataValue::from(i64::MIN)
+    );
+    assert!(op_to_int(&[DataValue::List(vec![])]).is_err());
+    assert!(op_to_int(&[DataValue::from(true)]).is_err());
+}
+
+#[test]
+fn test_number_format() {
+    assert_eq!(
+        op_number_format(&[DataValue::from(1.23456), DataValue::from(2)]).unwrap(),
+        DataValue::from("1.23")
+    );
+    assert_eq!(
+        op_number_format(&[DataValue::from(2.675), DataValue::from(1)]).unwrap(),
+        DataValue::from("2.7")
+    );
+    assert_eq!(
+        op_number_format(&[DataValue::from(0.125), DataValue::from(2)]).unwrap(),
+        DataValue::from("0.12")
+    );
+    assert_eq!(
+        op_number_format(&[DataValue::from(7), DataValue::from(3)]).unwrap(),
+        DataValue::from("7.000")
+    );
+    assert_eq!(
+        op_number_format(&[DataValue::from(2.5), DataValue::from(0)]).unwrap(),
+        DataValue::from("2")
+    );
+    assert_eq!(
+        op_number_format(&[DataValue::from(-1.96), DataValue::from(0)]).unwrap(),
+        DataValue::from("-2")
+    );
+    assert!(op_number_format(&[DataValue::from("1"), DataValue::from(2)]).is_err());
+    assert!(op_number_format(&[DataValue::from(1.0), DataValue::from(-1)]).is_err());
+}
+
+#[test]
+fn test_chars() {
+    assert_eq!(
+        op_chars(&[DataValue::from("ab")]).unwrap(),
+        DataValue::List(vec![DataValue::from("a"), DataValue::from("b")])
+    );
+    assert_eq!(
+        op_chars(&[DataValue::from("")]).unwrap(),
+        DataValue::List(vec![])
+    );
+    let s = "héllo, 世界 \u{1F600}";
+    let chars = op_chars(&[DataValue::from(s)]).unwrap();
+    assert_eq!(chars.get_slice().unwrap().len(), s.chars().count());
+    assert_eq!(chars.get_slice().unwrap()[7], DataValue::from("世"));
+    assert!(op_chars(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_byte_length() {
+    let len = |s: &str| op_byte_length(&[DataValue::from(s)]).unwrap();
+    assert_eq!(len(""), DataValue::from(0));
+    assert_eq!(len("cafe"), DataValue::from(4));
+    // 'é' is one character but two bytes
+    let chars = op_chars(&[DataValue::from("caf\u{e9}")]).unwrap();
+    assert_eq!(chars.get_slice().unwrap().len(), 4);
+    assert_eq!(len("caf\u{e9}"), DataValue::from(5));
+    assert_eq!(len("\u{1F600}"), DataValue::from(4));
+    assert!(op_byte_length(&[DataValue::Bytes(vec![1, 2])]).is_err());
+}
+
+#[test]
+fn test_zero_fill() {
+    let fill = |v: DataValue, n: i64| op_zero_fill(&[v, DataValue::from(n)]);
+    assert_eq!(fill(DataValue::from(42), 5).unwrap(), DataValue::from("00042"));
+    assert_eq!(fill(DataValue::from(-42), 5).unwrap(), DataValue::from("-0042"));
+    assert_eq!(fill(DataValue::from(0), 3).unwrap(), DataValue::from("000"));
+    assert_eq!(fill(DataValue::from(12345), 3).unwrap(), DataValue::from("12345"));
+    assert_eq!(fill(DataValue::from(-123), 4).unwrap(), DataValue::from("-123"));
+    assert_eq!(fill(DataValue::from(7), 0).unwrap(), DataValue::from("7"));
+    assert_eq!(
+        fill(DataValue::from(i64::MIN), 0).unwrap(),
+        DataValue::from(i64::MIN.to_string())
+    );
+    assert_eq!(fill(DataValue::from("-7"), 4).unwrap(), DataValue::from("-007"));
+    assert_eq!(fill(DataValue::from("007"), 5).unwrap(), DataValue::from("00007"));
+    assert!(fill(DataValue::from("12a"), 5).is_err());
+    assert!(fill(DataValue::from("-"), 5).is_err());
+    assert!(fill(DataValue::from(1.5), 5).is_err());
+    assert!(fill(DataValue::from(1), -1).is_err());
+    assert!(fill(DataValue::from(1), 100_000_000_000_000).is_err());
+}
+
+#[test]
+fn test_count_substr() {
+    let count = |h: &str, n: &str| op_count_substr(&[DataValue::from(h), DataValue::from(n)]);
+    assert_eq!(count("banana", "an").unwrap(), DataValue::from(2));
+    assert_eq!(count("banana", "x").unwrap(), DataValue::from(0));
+    assert_eq!(count("", "x").unwrap(), DataValue::from(0));
+    assert_eq!(count("aaaa", "aa").unwrap(), DataValue::from(2));
+    assert_eq!(count("aaa", "aa").unwrap(), DataValue::from(1));
+    assert_eq!(count("abc", "abc").unwrap(), DataValue::from(1));
+    assert_eq!(count("日本日本", "日").unwrap(), DataValue::from(2));
+    assert!(count("abc", "").is_err());
+    assert!(op_count_substr(&[DataValue::from(1), DataValue::from("1")]).is_err());
+}
+
+#[test]
+fn test_positions() {
+    let ints = |xs: &[i64]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    let l = DataValue::List([1, 2, 1, 3, 1].into_iter().map(DataValue::from).collect());
+    assert_eq!(op_positions(&[l.clone(), DataValue::from(1)]).unwrap(), ints(&[0, 2, 4]));
+    assert_eq!(op_positions(&[l, DataValue::from(5)]).unwrap(), ints(&[]));
+
+    let pos = |h: &str, n: &str| op_positions(&[DataValue::from(h), DataValue::from(n)]);
+    assert_eq!(pos("abcabc", "bc").unwrap(), ints(&[1, 4]));
+    assert_eq!(pos("aaaa", "aa").unwrap(), ints(&[0, 2]));
+    assert_eq!(pos("日本の日本", "日本").unwrap(), ints(&[0, 3]));
+    assert_eq!(pos("abc", "x").unwrap(), ints(&[]));
+    assert!(pos("abc", "").is_err());
+    assert!(op_positions(&[DataValue::from("abc"), DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_levenshtein() {
+    let dist = |a: &str, b: &str| op_levenshtein(&[DataValue::from(a), DataValue::from(b)]);
+    assert_eq!(dist("kitten", "kitten").unwrap(), DataValue::from(0));
+    assert_eq!(dist("", "").unwrap(), DataValue::from(0));
+    assert_eq!(dist("cat", "cart").unwrap(), DataValue::from(1));
+    assert_eq!(dist("cat", "cut").unwrap(), DataValue::from(1));
+    assert_eq!(dist("kitten", "sitting").unwrap(), DataValue::from(3));
+    assert_eq!(dist("abc", "xyz").unwrap(), DataValue::from(3));
+    assert_eq!(dist("", "abc").unwrap(), DataValue::from(3));
+    assert_eq!(dist("日本", "日本語").unwrap(), DataValue::from(1));
+    assert!(op_levenshtein(&[DataValue::from("a"), DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_trigram_similarity() {
+    let sim = |a: &str, b: &str| {
+        op_trigram_similarity(&[DataValue::from(a), DataValue::from(b)])
+            .unwrap()
+            .get_float()
+            .unwrap()
+    };
+    assert_eq!(sim("hello", "hello"), 1.0);
+    assert_eq!(sim("a", "a"), 1.0);
+    assert_eq!(sim("", ""), 1.0);
+    assert_eq!(sim("abc", "xyz"), 0.0);
+    assert_eq!(sim("", "abc"), 0.0);
+    // {"  a", " ab", "abc", "bc "} vs {"  a", " ab", "abd", "bd "}
+    assert!((sim("abc", "abd") - 2. / 6.).abs() < 1e-9);
+    assert_eq!(sim("night", "nacht"), sim("nacht", "night"));
+    assert!(op_trigram_similarity(&[DataValue::from("a"), DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_wrap() {
+    let wrap = |s: &str, width: i64| op_wrap(&[DataValue::from(s), DataValue::from(width)]);
+    assert_eq!(
+        wrap("the quick brown fox jumps over the lazy dog", 10).unwrap(),
+        DataValue::from("the quick\nbrown fox\njumps over\nthe lazy\ndog")
+    );
+    assert_eq!(
+        wrap("a supercalifragilistic word", 8).unwrap(),
+        DataValue::from("a\nsupercal\nifragili\nstic\nword")
+    );
+    assert_eq!(wrap("ab c", 1).unwrap(), DataValue::from("a\nb\nc"));
+    assert_eq!(wrap("日本語 テキスト", 3).unwrap(), DataValue::from("日本語\nテキス\nト"));
+    assert_eq!(wrap("  spaced \n  out  ", 20).unwrap(), DataValue::from("spaced out"));
+    assert_eq!(wrap("", 5).unwrap(), DataValue::from(""));
+    assert!(wrap("abc", 0).is_err());
+}
+
+#[test]
+fn test_lines_unlines() {
+    let strs = |xs: &[&str]| DataValue::List(xs.iter().map(|x| DataValue::from(*x)).collect());
+    let lines = |s: &str| op_lines(&[DataValue::from(s)]).unwrap();
+    assert_eq!(lines("a\nb\r\nc"), strs(&["a", "b", "c"]));
+    assert_eq!(lines("a\nb\n"), strs(&["a", "b"]));
+    assert_eq!(lines("a\n\nb"), strs(&["a", "", "b"]));
+    assert_eq!(lines(""), strs(&[]));
+    assert!(op_lines(&[DataValue::from(1)]).is_err());
+
+    let unlines = |l: DataValue| op_unlines(&[l]).unwrap();
+    assert_eq!(unlines(strs(&["a", "b"])), DataValue::from("a\nb"));
+    assert_eq!(unlines(strs(&[])), DataValue::from(""));
+    assert_eq!(unlines(lines("x\r\ny\n")), DataValue::from("x\ny"));
+    assert!(op_unlines(&[DataValue::List(vec![DataValue::from(1)])]).is_err());
+}
+
+#[test]
+fn test_pad_center() {
+    let pad = |s: &str, n: i64, f: &str| {
+        op_pad_center(&[DataValue::from(s), DataValue::from(n), DataValue::from(f)])
+    };
+    assert_eq!(pad("ab", 6, "*").unwrap(), DataValue::from("**ab**"));
+    assert_eq!(pad("ab", 5, "*").unwrap(), DataValue::from("*ab**"));
+    assert_eq!(pad("abc", 2, "*").unwrap(), DataValue::from("abc"));
+    assert_eq!(pad("abc", 3, "*").unwrap(), DataValue::from("abc"));
+    assert_eq!(pad("世界", 5, "·").unwrap(), DataValue::from("·世界··"));
+    assert_eq!(pad("", 2, "é").unwrap(), DataValue::from("éé"));
+    assert!(pad("ab", 5, "").is_err());
+    assert!(pad("ab", 5, "xy").is_err());
+    assert!(pad("ab", -1, "*").is_err());
+}
+
+#[test]
+fn test_str_reverse_graphemes() {
+    assert_eq!(
+        op_str_reverse_graphemes(&[DataValue::from("abc")]).unwrap(),
+        DataValue::from("cba")
+    );
+    assert_eq!(
+        op_str_reverse_graphemes(&[DataValue::from("")]).unwrap(),
+        DataValue::from("")
+    );
+    // 'e' followed by a combining acute accent stays a single cluster
+    assert_eq!(
+        op_str_reverse_graphemes(&[DataValue::from("cafe\u{301}!")]).unwrap(),
+        DataValue::from("!e\u{301}fac")
+    );
+    // family emoji joined by ZWJ is kept intact
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    assert_eq!(
+        op_str_reverse_graphemes(&[DataValue::from(format!("a{family}b"))]).unwrap(),
+        DataValue::from(format!("b{family}a"))
+    );
+    assert!(op_str_reverse_graphemes(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_unicode_normalize() {
+    let norm = |s: &str, form: &str| {
+        op_unicode_normalize(&[DataValue::from(s), DataValue::from(form)]).unwrap()
+    };
+    let composed = "caf\u{e9}";
+    let decomposed = "cafe\u{301}";
+    assert_eq!(norm(composed, "NFC"), norm(decomposed, "NFC"));
+    assert_eq!(norm(decomposed, "NFC"), DataValue::from(composed));
+    assert_eq!(norm(composed, "NFD"), DataValue::from(decomposed));
+    // compatibility forms fold ligatures
+    assert_eq!(norm("\u{fb01}", "NFKC"), DataValue::from("fi"));
+    assert_eq!(norm("\u{fb01}", "NFC"), DataValue::from("\u{fb01}"));
+    assert!(op_unicode_normalize(&[DataValue::from("a"), DataValue::from("NFX")]).is_err());
+    assert!(op_unicode_normalize(&[DataValue::from(1), DataValue::from("NFC")]).is_err());
+}
+
+#[test]
+fn test_casefold() {
+    let fold = |s: &str| op_casefold(&[DataValue::from(s)]).unwrap();
+    assert_eq!(fold("\u{df}"), DataValue::from("ss"));
+    assert_eq!(fold("Stra\u{df}e"), fold("STRASSE"));
+    assert_eq!(fold("Hello World"), fold("hELLO wORLD"));
+    assert_ne!(fold("abc"), fold("abd"));
+    assert!(op_casefold(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_regex_matches() {
+    assert_eq!(
+        op_regex_matches(&[DataValue::from("abc123"), DataValue::from(r"^[a-z]+\d+$")]).unwrap(),
+        DataValue::from(true)
+    );
+    assert_eq!(
+        op_regex_matches(&[DataValue::from("abc"), DataValue::from(r"\d")]).unwrap(),
+        DataValue::from(false)
+    );
+    // the second use of a pattern hits the cache
+    assert_eq!(
+        op_regex_matches(&[DataValue::from("x9"), DataValue::from(r"\d")]).unwrap(),
+        DataValue::from(true)
+    );
+    let err = op_regex_matches(&[DataValue::from("abc"), DataValue::from("(unclosed")]).unwrap_err();
+    assert!(err.to_string().contains("invalid regex"));
+    assert!(op_regex_matches(&[DataValue::from(1), DataValue::from("1")]).is_err());
+}
+
+#[test]
+fn test_regex_extract_replace() {
+    assert_eq!(
+        op_regex_extract(&[DataValue::from("order #42 shipped"), DataValue::from(r"#(\d+)")])
+            .unwrap(),
+        DataValue::from("42")
+    );
+    assert_eq!(
+        op_regex_extract(&[DataValue::from("order #42 shipped"), DataValue::from(r"\d+")])
+            .unwrap(),
+        DataValue::from("42")
+    );
+    assert_eq!(
+        op_regex_extract(&[DataValue::from("no digits"), DataValue::from(r"(\d+)")]).unwrap(),
+        DataValue::Null
+    );
+    assert!(op_regex_extract(&[DataValue::from("a"), DataValue::from("[")]).is_err());
+
+    assert_eq!(
+        op_regex_replace(&[
+            DataValue::from("2023-01-15"),
+            DataValue::from(r"(\d+)-(\d+)-(\d+)"),
+            DataValue::from("$3/$2/$1")
+        ])
+        .unwrap(),
+        DataValue::from("15/01/2023")
+    );
+    assert_eq!(
+        op_regex_replace(&[
+            DataValue::from("a1b22c"),
+            DataValue::from(r"\d+"),
+            DataValue::from("#")
+        ])
+        .unwrap(),
+        DataValue::from("a#b#c")
+    );
+    assert!(op_regex_replace(&[
+        DataValue::from("a"),
+        DataValue::from("("),
+        DataValue::from("")
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_rand_seeded() {
+    let draw = || {
+        (0..5)
+            .map(|_| {
+                (
+                    op_rand_float(&[]).unwrap(),
+                    op_rand_int(&[DataValue::from(-3), DataValue::from(7)]).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    set_rand_seed(Some(42));
+    let first = draw();
+    set_rand_seed(Some(42));
+    assert_eq!(draw(), first);
+    set_rand_seed(None);
+
+    for (f, i) in first {
+        let f = f.get_float().unwrap();
+        assert!((0. ..1.).contains(&f));
+        let i = i.get_int().unwrap();
+        assert!((-3..7).contains(&i));
+    }
+    assert_eq!(
+        op_rand_int(&[DataValue::from(5), DataValue::from(6)]).unwrap(),
+        DataValue::from(5)
+    );
+    assert!(op_rand_int(&[DataValue::from(5), DataValue::from(5)]).is_err());
+    assert!(op_rand_int(&[DataValue::from(0.5), DataValue::from(5)]).is_err());
+}
+
+#[test]
+fn test_sample() {
+    let l = DataValue::List((0..10).map(DataValue::from).collect());
+    let sample = |n: i64| op_sample(&[l.clone(), DataValue::from(n)]).unwrap();
+    set_rand_seed(Some(7));
+    let first = sample(4);
+    set_rand_seed(Some(7));
+    assert_eq!(sample(4), first);
+
+    let picked = first.get_slice().unwrap();
+    assert_eq!(picked.len(), 4);
+    assert_eq!(picked.iter().collect::<BTreeSet<_>>().len(), 4);
+    assert!(picked.iter().all(|v| (0..10).contains(&v.get_int().unwrap())));
+
+    let mut all = sample(20).get_slice().unwrap().to_vec();
+    all.sort();
+    assert_eq!(DataValue::List(all), l);
+    assert_eq!(sample(0), DataValue::List(vec![]));
+
+    // every element is equally likely to be picked, not just the leading ones
+    let short = DataValue::List((0..4).map(DataValue::from).collect());
+    let mut counts = [0; 4];
+    for _ in 0..400 {
+        let picked = op_sample(&[short.clone(), DataValue::from(1)]).unwrap();
+        counts[picked.get_slice().unwrap()[0].get_int().unwrap() as usize] += 1;
+    }
+    assert!(counts.iter().all(|c| *c > 50), "{counts:?}");
+    set_rand_seed(None);
+
+    assert!(op_sample(&[l.clone(), DataValue::from(-1)]).is_err());
+    assert!(op_sample(&[DataValue::from("abc"), DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_shuffle() {
+    let l = DataValue::List((1..=5).map(DataValue::from).collect());
+    set_rand_seed(Some(3));
+    let shuffled = op_shuffle(std::slice::from_ref(&l)).unwrap();
+    set_rand_seed(None);
+    let expected = DataValue::List([3, 5, 2, 1, 4].into_iter().map(DataValue::from).collect());
+    assert_eq!(shuffled, expected);
+
+    let mut elements = shuffled.get_slice().unwrap().to_vec();
+    elements.sort();
+    assert_eq!(DataValue::List(elements), l);
+    assert_eq!(
+        op_shuffle(&[DataValue::List(vec![])]).unwrap(),
+        DataValue::List(vec![])
+    );
+    assert!(op_shuffle(&[DataValue::from(1)]).is_err());
+}
+
 #[test]
 fn test_uuid() {
     // // let v1 = op_rand_uuid_v1(&[]).unwrap();
@@ -355,4 +1766,34 @@ fn test_now() {
     let _dt = op_parse_timestamp(&[s]).unwrap();
 }
 
+#[test]
+fn test_validity() {
+    let v = op_validity(&[DataValue::from(1_700_000_000_123i64), DataValue::Bool(true)]).unwrap();
+    assert_eq!(v, DataValue::Validity(Validity::from((1_700_000_000_123_000, true))));
+    assert_eq!(
+        op_validity_to_millis(&[v]).unwrap(),
+        DataValue::from(1_700_000_000_123i64)
+    );
+
+    let retract = op_validity(&[DataValue::from(0), DataValue::Bool(false)]).unwrap();
+    match &retract {
+        DataValue::Validity(vld) => assert!(!vld.is_assert.0),
+        v => panic!("expected a validity, got {v:?}"),
+    }
+    assert_eq!(op_validity_to_millis(&[retract]).unwrap(), DataValue::from(0));
+
+    assert!(op_validity(&[DataValue::from(i64::MAX), DataValue::Bool(true)]).is_err());
+    assert!(op_validity(&[DataValue::from(1), DataValue::from(1)]).is_err());
+    assert!(op_validity_to_millis(&[DataValue::from(1)]).is_err());
+}
+
+#[test]
+fn test_now_micros() {
+    let now = op_now_micros(&[]).unwrap();
+    let micros = now.get_int().expect("'now_micros' should return an integer");
+    // 2024-01-01T00:00:00Z
+    assert!(micros > 1_704_067_200_000_000);
+    assert!(op_now_micros(&[]).unwrap().get_int().unwrap() >= micros);
+}
+
 
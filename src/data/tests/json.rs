@@ -10,7 +10,7 @@
 use serde_json::json;
 
 use crate::data::json::JsonValue;
-use crate::data::value::DataValue;
+use crate::data::value::{DataValue, JsonData, Validity};
 
 #[test]
 fn bad_values() {
@@ -19,3 +19,46 @@ fn bad_values() {
     println!("{}", JsonValue::from(DataValue::from(f64::NEG_INFINITY)));
     println!("{}", JsonValue::from(DataValue::from(f64::NAN)));
 }
+
+#[test]
+fn to_json_value_encodings() {
+    let id = uuid::Uuid::from_u128(0x0102030405060708090a0b0c0d0e0f10);
+    assert_eq!(
+        DataValue::uuid(id).to_json_value(),
+        json!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16])
+    );
+    let list = DataValue::List(vec![
+        DataValue::from(1),
+        DataValue::from("a"),
+        DataValue::Null,
+        DataValue::List(vec![DataValue::from(true), DataValue::from(2.5)]),
+    ]);
+    assert_eq!(list.to_json_value(), json!([1, "a", null, [true, 2.5]]));
+    let vld = DataValue::Validity(Validity::from((10, true)));
+    assert_eq!(vld.to_json_value(), json!([10, true]));
+}
+
+#[test]
+fn from_json_value_shallow_and_deep() {
+    let nested = json!([1, "a", [2.5, [null]], {"k": [true]}]);
+    assert_eq!(
+        DataValue::from_json_value(&nested),
+        DataValue::Json(JsonData(nested.clone()))
+    );
+    assert_eq!(DataValue::from_json_value(&json!(3)), DataValue::from(3));
+    assert_eq!(DataValue::from_json_value(&json!("s")), DataValue::from("s"));
+
+    assert_eq!(
+        DataValue::from_json_deep(&nested),
+        DataValue::List(vec![
+            DataValue::from(1),
+            DataValue::from("a"),
+            DataValue::List(vec![
+                DataValue::from(2.5),
+                DataValue::List(vec![DataValue::Null])
+            ]),
+            DataValue::Json(JsonData(json!({"k": [true]}))),
+        ])
+    );
+    assert_eq!(DataValue::from_json_deep(&nested).to_json_value(), nested);
+}
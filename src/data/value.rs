@@ -15,6 +15,7 @@ use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 
+use crate::data::functions::{json2val, json2val_deep, to_json};
 use crate::data::json::JsonValue;
 // use crate::data::relation::VecElementType;
 use ordered_float::OrderedFloat;
@@ -583,6 +584,23 @@ impl Display for DataValue {
 }
 
 impl DataValue {
+    /// Converts to JSON, e.g. for building responses from query output.
+    /// UUIDs become arrays of their 16 bytes, and validities become
+    /// `[timestamp, is_assert]` pairs.
+    pub fn to_json_value(&self) -> JsonValue {
+        to_json(self)
+    }
+    /// Converts from JSON, e.g. for building query parameters. Scalars map to their
+    /// native counterparts, but arrays and objects are kept whole as `DataValue::Json`;
+    /// use [`DataValue::from_json_deep`] to get native lists instead.
+    pub fn from_json_value(value: &JsonValue) -> Self {
+        json2val(value.clone())
+    }
+    /// Like [`DataValue::from_json_value`], but converts arrays to `DataValue::List`
+    /// recursively. Objects are still kept as `DataValue::Json`.
+    pub fn from_json_deep(value: &JsonValue) -> Self {
+        json2val_deep(value)
+    }
     /// Returns a slice of bytes if this one is a Bytes
     pub fn get_bytes(&self) -> Option<&[u8]> {
         match self {
@@ -2,9 +2,85 @@ use itertools::Itertools;
 use serde_json::json;
 use miette::{bail, ensure, Context, Diagnostic, Error, IntoDiagnostic, Result};
 
-use crate::{compile::{compile::{FilteredRA, ReorderRA, UnificationRA}, CompiledProgram, CompiledRule, CompiledRuleSet, InnerJoin, RelAlgebra, StoredRA, TempStoreRA}, data::{json::JsonValue, value::DataValue}, runtime::db::NamedRows};
+use crate::{compile::{compile::{FilteredRA, ReorderRA, UnificationRA}, expr::Expr, CompiledProgram, CompiledRule, CompiledRuleSet, InnerJoin, RelAlgebra, StoredRA, TempStoreRA}, data::{json::JsonValue, value::DataValue}, runtime::db::NamedRows};
 
+/// Describes a compiled program as a table, with a row for each node of the relational
+/// algebra tree of each rule.
 pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
+    explain_compiled_impl(strata, false)
+}
+
+/// Like [`explain_compiled`], but bindings in filters and expressions are shown
+/// with their resolved tuple positions, e.g. `gt(b@1, 1)`.
+pub fn explain_compiled_verbose(strata: &[CompiledProgram]) -> Result<NamedRows> {
+    explain_compiled_impl(strata, true)
+}
+
+fn format_expr(expr: &Expr, verbose: bool) -> String {
+    if !verbose {
+        return expr.to_string();
+    }
+    let format_args = |args: &[Expr]| args.iter().map(|a| format_expr(a, true)).join(", ");
+    match expr {
+        Expr::Binding {
+            var,
+            tuple_pos: Some(pos),
+        } => format!("{}@{}", var.name, pos),
+        Expr::Binding {
+            var,
+            tuple_pos: None,
+        } => var.name.to_string(),
+        Expr::Const { val, .. } => val.to_string(),
+        Expr::Apply { op, args, .. } => format!(
+            "{}({})",
+            op.name.strip_prefix("OP_").unwrap_or(op.name).to_lowercase(),
+            format_args(args)
+        ),
+        Expr::UnboundApply { op, args, .. } => format!("{}({})", op, format_args(args)),
+        Expr::Cond { clauses, .. } => format!(
+            "cond({})",
+            clauses
+                .iter()
+                .flat_map(|(cond, expr)| [format_expr(cond, true), format_expr(expr, true)])
+                .join(", ")
+        ),
+    }
+}
+
+/// Like [`explain_compiled`], but serialized as a JSON array holding one object per row,
+/// keyed by column name.
+pub fn explain_compiled_json(strata: &[CompiledProgram]) -> Result<String> {
+    let (headers, rows) = explain_rows(strata, false)?;
+    let objects = rows
+        .into_iter()
+        .map(|row| {
+            headers
+                .iter()
+                .map(|h| (h.clone(), row.get(h).cloned().unwrap_or(JsonValue::Null)))
+                .collect::<serde_json::Map<_, _>>()
+        })
+        .collect_vec();
+    serde_json::to_string(&objects).into_diagnostic()
+}
+
+fn explain_compiled_impl(strata: &[CompiledProgram], verbose: bool) -> Result<NamedRows> {
+    let (headers, ret) = explain_rows(strata, verbose)?;
+    let rows = ret
+        .into_iter()
+        .map(|m| {
+            headers
+                .iter()
+                .map(|i| DataValue::from(m.get(i).unwrap_or(&JsonValue::Null)))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    Ok(NamedRows::new(headers, rows))
+}
+
+/// The column names of the explain table, and one JSON object per row keyed by them.
+/// Columns that do not apply to a row are left out of its object.
+fn explain_rows(strata: &[CompiledProgram], verbose: bool) -> Result<(Vec<String>, Vec<JsonValue>)> {
     let mut ret: Vec<JsonValue> = vec![];
     const STRATUM: &str = "stratum";
     const ATOM_IDX: &str = "atom_idx";
@@ -13,7 +89,10 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
     const RULE_NAME: &str = "rule";
     const REF_NAME: &str = "ref";
     const OUT_BINDINGS: &str = "out_relation";
+    const ELIMINATED: &str = "eliminated";
     const JOINS_ON: &str = "joins_on";
+    const DRIVING_SIDE: &str = "driving_side";
+    const JOIN_ORDER: &str = "join_order";
     const FILTERS: &str = "filters/expr";
 
     let headers = vec![
@@ -24,8 +103,11 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
         OP.to_string(),
         REF_NAME.to_string(),
         JOINS_ON.to_string(),
+        DRIVING_SIDE.to_string(),
+        JOIN_ORDER.to_string(),
         FILTERS.to_string(),
         OUT_BINDINGS.to_string(),
+        ELIMINATED.to_string(),
     ];
 
     for (stratum, p) in strata.iter().enumerate() {
@@ -55,7 +137,8 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                             OP: atom_type,
                             RULE_IDX: clause_idx,
                             RULE_NAME: rule_name.to_string(),
-                            OUT_BINDINGS: relation.bindings_after_eliminate().into_iter().map(|v| v.to_string()).collect_vec()
+                            OUT_BINDINGS: relation.bindings_after_eliminate().into_iter().map(|v| v.to_string()).collect_vec(),
+                            ELIMINATED: relation.eliminate_set().map(|s| s.iter().map(|v| v.to_string()).collect_vec()),
                         }));
                         idx += 1;
 
@@ -75,7 +158,7 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                                     "load_mem",
                                     json!(storage_key.to_string()),
                                     json!(null),
-                                    json!(filters.iter().map(|f| f.to_string()).collect_vec()),
+                                    json!(filters.iter().map(|f| format_expr(f, verbose)).collect_vec()),
                                 ),
                                 RelAlgebra::Stored(StoredRA {
                                     name, filters, ..
@@ -83,14 +166,20 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                                     "load_stored",
                                     json!(format!(":{}", name)),
                                     json!(null),
-                                    json!(filters.iter().map(|f| f.to_string()).collect_vec()),
+                                    json!(filters.iter().map(|f| format_expr(f, verbose)).collect_vec()),
                                 ),
                                 RelAlgebra::Join(inner) => {
                                     if inner.left.is_unit() {
                                         rel_stack.push(&inner.right);
                                         continue;
                                     }
-                                    let t = inner.join_type();
+                                    let t = if inner.self_join {
+                                        "self_join"
+                                    } else if inner.cartesian {
+                                        "cartesian_join"
+                                    } else {
+                                        inner.join_type()
+                                    };
                                     let InnerJoin {
                                         left,
                                         right,
@@ -115,7 +204,7 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                                         "filter",
                                         json!(null),
                                         json!(null),
-                                        json!(pred.iter().map(|f| f.to_string()).collect_vec()),
+                                        json!(pred.iter().map(|f| format_expr(f, verbose)).collect_vec()),
                                     )
                                 }
                                 RelAlgebra::Unification(UnificationRA {
@@ -130,10 +219,14 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                                         if *is_multi { "multi-unify" } else { "unify" },
                                         json!(binding.name),
                                         json!(null),
-                                        json!(expr.to_string()),
+                                        json!(format_expr(expr, verbose)),
                                     )
                                 }
                             };
+                            let driving_side = match rel {
+                                RelAlgebra::Join(inner) => Some(inner.driving_side()),
+                                _ => None,
+                            };
                             ret_for_relation.push(json!({
                                 STRATUM: stratum,
                                 ATOM_IDX: idx,
@@ -142,12 +235,23 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
                                 RULE_NAME: rule_name.to_string(),
                                 REF_NAME: ref_name,
                                 OUT_BINDINGS: rel.bindings_after_eliminate().into_iter().map(|v| v.to_string()).collect_vec(),
+                                ELIMINATED: rel.eliminate_set().map(|s| s.iter().map(|v| v.to_string()).collect_vec()),
                                 JOINS_ON: joins_on,
+                                DRIVING_SIDE: driving_side,
                                 FILTERS: filters,
                             }));
                             idx += 1;
                         }
                         ret_for_relation.reverse();
+                        // joins are numbered from the innermost one outwards, following the
+                        // left-deep shape of the plan
+                        let mut join_order = 0;
+                        for row in ret_for_relation.iter_mut() {
+                            if row.get(JOINS_ON).is_some_and(|j| !j.is_null()) {
+                                row[JOIN_ORDER] = json!(join_order);
+                                join_order += 1;
+                            }
+                        }
                         ret.extend(ret_for_relation)
                     }
                 }
@@ -162,17 +266,230 @@ pub fn explain_compiled(strata: &[CompiledProgram]) -> Result<NamedRows> {
         }
     }
 
-    let rows = ret
-        .into_iter()
-        .map(|m| {
-            headers
-                .iter()
-                .map(|i| DataValue::from(m.get(i).unwrap_or(&JsonValue::Null)))
+    Ok((headers, ret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::expr::Op;
+    use crate::compile::Compiler;
+    use crate::data::functions::op_list;
+
+    fn explain_ops(compiler: &mut Compiler, script: &str) -> Vec<DataValue> {
+        let compiled = compiler.compile_script(script).unwrap();
+        let explained = explain_compiled(&compiled).unwrap();
+        let op_idx = explained.headers.iter().position(|h| h == "op").unwrap();
+        explained.rows.into_iter().map(|row| row[op_idx].clone()).collect()
+    }
+
+    #[test]
+    fn repeated_variable_is_reported_as_self_join() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        compiler.compile_script(":create a {k: Int}").unwrap();
+
+        let ops = explain_ops(&mut compiler, "?[x] := *a[x], *kv[x, x]");
+        assert!(ops.contains(&DataValue::from("self_join")));
+
+        let ops = explain_ops(&mut compiler, "r[a, count(b)] := *kv[a, b]\n?[x] := *a[x], r[x, x]");
+        assert!(ops.contains(&DataValue::from("self_join")));
+
+        let ops = explain_ops(&mut compiler, "?[k, v] := *kv[k, v]");
+        assert!(!ops.contains(&DataValue::from("self_join")));
+    }
+
+    #[test]
+    fn repeat_in_first_atom_or_user_equality_is_not_a_join() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+
+        // with nothing to join against, the repeat is a filter on the scan
+        let ops = explain_ops(&mut compiler, "?[x] := *kv[x, x]");
+        assert_eq!(ops, vec![DataValue::from("load_stored"), DataValue::from("out")]);
+
+        let ops = explain_ops(&mut compiler, "?[a] := *kv[a, b], b = a");
+        assert_eq!(ops, vec![DataValue::from("load_stored"), DataValue::from("out")]);
+
+        let compiled = compiler
+            .compile_script("?[a] := *kv[a, b], *kv[b, c], c = a")
+            .unwrap();
+        let explained = explain_compiled(&compiled).unwrap();
+        assert!(!explained
+            .rows
+            .iter()
+            .any(|row| row.contains(&DataValue::from("self_join"))));
+    }
+
+    #[test]
+    fn join_without_shared_variables_is_reported_as_cartesian() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create a {x: Int}").unwrap();
+        compiler.compile_script(":create b {y: Int}").unwrap();
+
+        let ops = explain_ops(&mut compiler, "?[x, y] := *a[x], *b[y]");
+        assert!(ops.contains(&DataValue::from("cartesian_join")));
+
+        let ops = explain_ops(&mut compiler, "r[y] := *b[y]\n?[x, y] := *a[x], r[y]");
+        assert!(ops.contains(&DataValue::from("cartesian_join")));
+
+        let ops = explain_ops(&mut compiler, "?[x] := *a[x], *b[x]");
+        assert!(!ops.contains(&DataValue::from("cartesian_join")));
+        let ops = explain_ops(&mut compiler, "?[x] := *a[x]");
+        assert!(!ops.contains(&DataValue::from("cartesian_join")));
+    }
+
+    #[test]
+    fn partially_bound_key_is_reported() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create seeds {a: Int}").unwrap();
+        compiler.compile_script(":create pairs {a: Int, b: Int}").unwrap();
+        compiler.compile_script(":create rel {a: Int, b: Int => c: Int}").unwrap();
+
+        let ops = explain_ops(&mut compiler, "?[a, b, c] := *seeds[a], *rel[a, b, c]");
+        assert!(ops.contains(&DataValue::from("stored_partial_prefix_join")));
+
+        let ops = explain_ops(&mut compiler, "?[a, b, c] := *pairs[a, b], *rel[a, b, c]");
+        assert!(ops.contains(&DataValue::from("stored_prefix_join")));
+        assert!(!ops.contains(&DataValue::from("stored_partial_prefix_join")));
+    }
+
+    #[test]
+    fn verbose_explain_shows_binding_indices() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[k] := *kv[k, v], v > 1, k != v")
+            .unwrap();
+
+        let filters_of = |explained: NamedRows| {
+            let idx = explained.headers.iter().position(|h| h == "filters/expr").unwrap();
+            explained
+                .rows
+                .into_iter()
+                .map(|row| row[idx].clone())
+                .filter(|f| *f != DataValue::Null)
                 .collect_vec()
-        })
-        .collect_vec();
+        };
+        let plain = filters_of(explain_compiled(&compiled).unwrap());
+        let verbose = filters_of(explain_compiled_verbose(&compiled).unwrap());
+        assert_eq!(
+            plain,
+            vec![DataValue::List(vec![
+                DataValue::from("gt(v, 1)"),
+                DataValue::from("neq(k, v)")
+            ])]
+        );
+        assert_eq!(
+            verbose,
+            vec![DataValue::List(vec![
+                DataValue::from("gt(v@1, 1)"),
+                DataValue::from("neq(k@0, v@1)")
+            ])]
+        );
+    }
 
-    Ok(NamedRows::new(headers, rows))
+    #[test]
+    fn verbose_op_name_without_prefix_is_kept() {
+        static CUSTOM: Op = Op {
+            name: "Custom",
+            min_arity: 0,
+            vararg: true,
+            inner: op_list,
+        };
+        let expr = Expr::Apply {
+            op: &CUSTOM,
+            args: [Expr::Const {
+                val: DataValue::from(1),
+                span: Default::default(),
+            }]
+            .into(),
+            span: Default::default(),
+        };
+        assert_eq!(format_expr(&expr, true), "custom(1)");
+    }
+
+    #[test]
+    fn filter_only_binding_is_reported_eliminated() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let compiled = compiler.compile_script("?[k] := *kv[k, v], v > 1").unwrap();
+        let explained = explain_compiled(&compiled).unwrap();
+        let col = |name: &str| explained.headers.iter().position(|h| h == name).unwrap();
+        let (op_idx, elim_idx, out_idx) = (col("op"), col("eliminated"), col("out_relation"));
+        let row_of = |op: &str| {
+            explained
+                .rows
+                .iter()
+                .find(|row| row[op_idx] == DataValue::from(op))
+                .unwrap()
+        };
+
+        // `v` is only needed by the filter pushed into the load, and dropped right after
+        let load = row_of("load_stored");
+        assert_eq!(
+            load[out_idx],
+            DataValue::List(vec![DataValue::from("k"), DataValue::from("v")])
+        );
+        assert_eq!(load[elim_idx], DataValue::Null);
+        let out = row_of("out");
+        assert_eq!(out[elim_idx], DataValue::List(vec![DataValue::from("v")]));
+        assert_eq!(out[out_idx], DataValue::List(vec![DataValue::from("k")]));
+    }
+
+    #[test]
+    fn json_explain_has_one_object_per_row() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create kv {k: Int => v: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("r[k] := *kv[k, v], v > 1\n?[k] := r[k]")
+            .unwrap();
+        let rows = explain_compiled(&compiled).unwrap();
+        let parsed: JsonValue =
+            serde_json::from_str(&explain_compiled_json(&compiled).unwrap()).unwrap();
+        let objects = parsed.as_array().unwrap();
+
+        assert_eq!(objects.len(), rows.rows.len());
+        for (obj, row) in objects.iter().zip(&rows.rows) {
+            let obj = obj.as_object().unwrap();
+            assert_eq!(obj.len(), rows.headers.len());
+            for (header, val) in rows.headers.iter().zip(row) {
+                assert_eq!(DataValue::from(&obj[header]), *val);
+            }
+        }
+    }
+
+    #[test]
+    fn join_order_follows_left_deep_plan() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create a {x: Int => y: Int}").unwrap();
+        compiler.compile_script(":create b {y: Int => z: Int}").unwrap();
+        compiler.compile_script(":create c {z: Int => w: Int}").unwrap();
+
+        let compiled = compiler
+            .compile_script("?[x, w] := *a[x, y], *b[y, z], *c[z, w]")
+            .unwrap();
+        let explained = explain_compiled(&compiled).unwrap();
+        let col = |name: &str| explained.headers.iter().position(|h| h == name).unwrap();
+        let (order_idx, side_idx) = (col("join_order"), col("driving_side"));
+
+        let orders = explained
+            .rows
+            .iter()
+            .filter_map(|row| row[order_idx].get_int())
+            .collect_vec();
+        assert_eq!(orders, vec![0, 1]);
+
+        // one driving side per join row, none on the scans
+        for row in &explained.rows {
+            let side = &row[side_idx];
+            if row[order_idx] == DataValue::Null {
+                assert_eq!(side, &DataValue::Null);
+            } else {
+                assert_eq!(side, &DataValue::from("left"));
+            }
+        }
+    }
 }
 
 
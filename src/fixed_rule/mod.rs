@@ -24,6 +24,7 @@ use miette::{bail, ensure, Diagnostic, Report, Result};
 use thiserror::Error;
 
 use crate::compile::expr::Expr;
+use crate::compile::program::{FixedRuleOptionNotFoundError, WrongFixedRuleOptionError};
 // // use crate::compile::program::{
 // //     // FixedRuleOptionNotFoundError, MagicFixedRuleApply, MagicFixedRuleRuleArg, MagicSymbol,
 // //     WrongFixedRuleOptionError,
@@ -43,6 +44,25 @@ use crate::runtime::db::NamedRows;
 // pub(crate) mod algos;
 pub(crate) mod utilities;
 
+lazy_static! {
+    /// The fixed rules every [Compiler](crate::Compiler) starts out with.
+    pub(crate) static ref DEFAULT_FIXED_RULES: BTreeMap<String, Arc<Box<dyn FixedRule>>> = {
+        BTreeMap::from([
+            (
+                "Distinct".to_string(),
+                Arc::<Box<dyn FixedRule>>::new(Box::new(Distinct)),
+            ),
+            (
+                "Limit".to_string(),
+                Arc::<Box<dyn FixedRule>>::new(Box::new(Limit)),
+            ),
+            (
+                "Union".to_string(),
+                Arc::<Box<dyn FixedRule>>::new(Box::new(Union)),
+            ),
+        ])
+    };
+}
 
 /// Trait for an implementation of an algorithm or a utility
 pub trait FixedRule: Send + Sync + Debug {
@@ -65,6 +85,18 @@ pub trait FixedRule: Send + Sync + Debug {
         rule_head: &[Symbol],
         span: SourceSpan,
     ) -> Result<usize>;
+    /// Called at compile time with the arity of each input relation, in order, and the
+    /// arity returned by [FixedRule::arity] for this application,
+    /// to reject inputs that the rule cannot work with.
+    /// The default implementation accepts any inputs.
+    fn check_input_arities(
+        &self,
+        _arities: &[usize],
+        _out_arity: usize,
+        _span: SourceSpan,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Simple wrapper for custom fixed rule. You have less control than implementing [FixedRule] directly,
@@ -219,3 +251,50 @@ pub(crate) struct BadExprValueError(
 #[diagnostic(code(parser::fixed_rule_not_found))]
 pub(crate) struct FixedRuleNotFoundError(pub(crate) String, #[label] pub(crate) SourceSpan);
 
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Fixed rule '{0}' requires {1} input relation(s) but was given {2}")]
+#[diagnostic(code(fixed_rule::wrong_input_count))]
+pub(crate) struct WrongInputRelationCountError(
+    pub(crate) String,
+    pub(crate) usize,
+    pub(crate) usize,
+    #[label] pub(crate) SourceSpan,
+);
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("The head of fixed rule '{0}' has {1} column(s) but its input relation has {2}")]
+#[diagnostic(code(fixed_rule::head_arity_mismatch))]
+#[diagnostic(help("The rule passes its input rows through, so the head must be as wide as the input"))]
+pub(crate) struct HeadArityMismatchError(
+    pub(crate) String,
+    pub(crate) usize,
+    pub(crate) usize,
+    #[label] pub(crate) SourceSpan,
+);
+
+/// Evaluates the option `name` given to the fixed rule `rule_name`, which must be a
+/// non-negative integer.
+pub(crate) fn non_neg_integer_option(
+    options: &BTreeMap<String, Expr>,
+    name: &str,
+    rule_name: &str,
+    span: SourceSpan,
+) -> Result<usize> {
+    let expr = options
+        .get(name)
+        .ok_or_else(|| FixedRuleOptionNotFoundError {
+            name: name.to_string(),
+            span,
+            rule_name: rule_name.to_string(),
+        })?;
+    match expr.clone().eval_to_const()?.get_non_neg_int() {
+        Some(i) => Ok(i as usize),
+        None => bail!(WrongFixedRuleOptionError {
+            name: name.to_string(),
+            span: expr.span(),
+            rule_name: rule_name.to_string(),
+            help: "a non-negative integer is required".to_string(),
+        }),
+    }
+}
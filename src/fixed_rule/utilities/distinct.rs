@@ -0,0 +1,144 @@
+/*
+ * Copyright 2022, The Cozo Project Authors.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0.
+ * If a copy of the MPL was not distributed with this file,
+ * You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use miette::{bail, ensure, Result};
+
+use crate::compile::expr::Expr;
+use crate::compile::symb::Symbol;
+use crate::data::tuple::Tuple;
+use crate::fixed_rule::{
+    CannotDetermineArity, FixedRule, HeadArityMismatchError, WrongInputRelationCountError,
+};
+use crate::parse::SourceSpan;
+use crate::runtime::db::NamedRows;
+
+/// Removes duplicate rows from its single input relation, keeping the first occurrence
+/// of each.
+#[derive(Debug)]
+pub struct Distinct;
+
+impl Distinct {
+    /// Applies the rule to its realized input relation.
+    pub fn run(&self, inputs: Vec<NamedRows>, span: SourceSpan) -> Result<NamedRows> {
+        ensure!(
+            inputs.len() == 1,
+            WrongInputRelationCountError("Distinct".to_string(), 1, inputs.len(), span)
+        );
+        let mut input = inputs.into_iter().next().unwrap();
+        let mut seen: BTreeSet<Tuple> = BTreeSet::new();
+        input.rows.retain(|row| seen.insert(row.clone()));
+        input.next = None;
+        Ok(input)
+    }
+}
+
+impl FixedRule for Distinct {
+    fn arity(
+        &self,
+        _options: &BTreeMap<String, Expr>,
+        rule_head: &[Symbol],
+        span: SourceSpan,
+    ) -> Result<usize> {
+        if rule_head.is_empty() {
+            bail!(CannotDetermineArity(
+                "Distinct".to_string(),
+                "the rule head is not given".to_string(),
+                span
+            ))
+        }
+        Ok(rule_head.len())
+    }
+
+    fn check_input_arities(
+        &self,
+        arities: &[usize],
+        out_arity: usize,
+        span: SourceSpan,
+    ) -> Result<()> {
+        ensure!(
+            arities.len() == 1,
+            WrongInputRelationCountError("Distinct".to_string(), 1, arities.len(), span)
+        );
+        ensure!(
+            arities[0] == out_arity,
+            HeadArityMismatchError("Distinct".to_string(), out_arity, arities[0], span)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::Compiler;
+    use crate::data::value::DataValue;
+
+    fn input(rows: Vec<(i64, &str)>) -> Vec<NamedRows> {
+        vec![NamedRows::new(
+            vec!["a".to_string(), "b".to_string()],
+            rows.into_iter()
+                .map(|(a, b)| vec![DataValue::from(a), DataValue::from(b)])
+                .collect(),
+        )]
+    }
+
+    #[test]
+    fn distinct_removes_duplicate_rows() {
+        let span = SourceSpan::default();
+        let out = Distinct.run(input(vec![(1, "x"); 3]), span).unwrap();
+        assert_eq!(out.rows, input(vec![(1, "x")])[0].rows);
+
+        let rows = vec![(2, "y"), (1, "x"), (2, "y"), (2, "x")];
+        let out = Distinct.run(input(rows), span).unwrap();
+        assert_eq!(out.rows, input(vec![(2, "y"), (1, "x"), (2, "x")])[0].rows);
+
+        let rows = vec![(3, "c"), (1, "a"), (2, "b")];
+        let out = Distinct.run(input(rows.clone()), span).unwrap();
+        assert_eq!(out.rows, input(rows)[0].rows);
+        assert_eq!(out.headers, vec!["a", "b"]);
+
+        assert!(Distinct.run(vec![], span).is_err());
+        assert!(Distinct
+            .arity(&BTreeMap::new(), &[], span)
+            .unwrap_err()
+            .downcast_ref::<CannotDetermineArity>()
+            .is_some());
+    }
+
+    #[test]
+    fn distinct_is_available_to_scripts() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create r {a: Int, b: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[a, b] <~ Distinct(*r[a, b])")
+            .unwrap();
+        let arity = compiled
+            .iter()
+            .flat_map(|prog| prog.values())
+            .map(|rule_set| rule_set.arity())
+            .next()
+            .unwrap();
+        assert_eq!(arity, 2);
+    }
+
+    #[test]
+    fn distinct_rejects_head_of_other_width_than_input() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create r {a: Int, b: Int}").unwrap();
+        let err = compiler
+            .compile_script("?[a, b, c] <~ Distinct(*r[a, b])")
+            .unwrap_err();
+        let mismatch = err.downcast_ref::<HeadArityMismatchError>().unwrap();
+        assert_eq!((mismatch.1, mismatch.2), (3, 2));
+        assert!(compiler
+            .compile_script("?[a] <~ Distinct(*r[a, b])")
+            .is_err());
+    }
+}
@@ -0,0 +1,180 @@
+/*
+ * Copyright 2022, The Cozo Project Authors.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0.
+ * If a copy of the MPL was not distributed with this file,
+ * You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+
+use miette::{bail, ensure, Result};
+
+use crate::compile::expr::Expr;
+use crate::compile::symb::Symbol;
+use crate::data::value::DataValue;
+use crate::fixed_rule::{
+    non_neg_integer_option, CannotDetermineArity, FixedRule, HeadArityMismatchError,
+    WrongInputRelationCountError,
+};
+use crate::parse::SourceSpan;
+use crate::runtime::db::NamedRows;
+
+/// Keeps the first `limit` rows of its single input relation.
+#[derive(Debug)]
+pub struct Limit;
+
+impl Limit {
+    /// Applies the rule to its realized input relation, with the options as given
+    /// to the rule application.
+    pub fn run(
+        &self,
+        inputs: Vec<NamedRows>,
+        options: &BTreeMap<String, Expr>,
+        span: SourceSpan,
+    ) -> Result<NamedRows> {
+        ensure!(
+            inputs.len() == 1,
+            WrongInputRelationCountError("Limit".to_string(), 1, inputs.len(), span)
+        );
+        let limit = non_neg_integer_option(options, "limit", "Limit", span)?;
+        let mut input = inputs.into_iter().next().unwrap();
+        input.rows.truncate(limit);
+        input.next = None;
+        Ok(input)
+    }
+}
+
+impl FixedRule for Limit {
+    fn init_options(&self, options: &mut BTreeMap<String, Expr>, span: SourceSpan) -> Result<()> {
+        let limit = non_neg_integer_option(options, "limit", "Limit", span)?;
+        options.insert(
+            "limit".to_string(),
+            Expr::Const {
+                val: DataValue::from(limit as i64),
+                span,
+            },
+        );
+        Ok(())
+    }
+
+    fn arity(
+        &self,
+        _options: &BTreeMap<String, Expr>,
+        rule_head: &[Symbol],
+        span: SourceSpan,
+    ) -> Result<usize> {
+        // the rows pass through unchanged, so the output is as wide as the input,
+        // which only the head can tell us before running
+        if rule_head.is_empty() {
+            bail!(CannotDetermineArity(
+                "Limit".to_string(),
+                "the rule head is not given".to_string(),
+                span
+            ))
+        }
+        Ok(rule_head.len())
+    }
+
+    fn check_input_arities(
+        &self,
+        arities: &[usize],
+        out_arity: usize,
+        span: SourceSpan,
+    ) -> Result<()> {
+        ensure!(
+            arities.len() == 1,
+            WrongInputRelationCountError("Limit".to_string(), 1, arities.len(), span)
+        );
+        ensure!(
+            arities[0] == out_arity,
+            HeadArityMismatchError("Limit".to_string(), out_arity, arities[0], span)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::program::WrongFixedRuleOptionError;
+    use crate::compile::Compiler;
+
+    fn limit_options(limit: i64) -> BTreeMap<String, Expr> {
+        BTreeMap::from([(
+            "limit".to_string(),
+            Expr::Const {
+                val: DataValue::from(limit),
+                span: SourceSpan::default(),
+            },
+        )])
+    }
+
+    fn input(n: i64) -> Vec<NamedRows> {
+        vec![NamedRows::new(
+            vec!["a".to_string(), "b".to_string()],
+            (0..n)
+                .map(|i| vec![DataValue::from(i), DataValue::from(i * 10)])
+                .collect(),
+        )]
+    }
+
+    #[test]
+    fn limit_keeps_leading_rows() {
+        let span = SourceSpan::default();
+        let out = Limit.run(input(5), &limit_options(2), span).unwrap();
+        assert_eq!(out.headers, vec!["a", "b"]);
+        assert_eq!(
+            out.rows,
+            vec![
+                vec![DataValue::from(0), DataValue::from(0)],
+                vec![DataValue::from(1), DataValue::from(10)]
+            ]
+        );
+        assert_eq!(Limit.run(input(3), &limit_options(10), span).unwrap().rows.len(), 3);
+        assert!(Limit.run(input(3), &limit_options(0), span).unwrap().rows.is_empty());
+        assert!(Limit.run(vec![], &limit_options(1), span).is_err());
+    }
+
+    #[test]
+    fn limit_takes_its_arity_from_the_head() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create r {a: Int, b: Int}").unwrap();
+        let compiled = compiler
+            .compile_script("?[a, b] <~ Limit(*r[a, b], limit: 1 + 1)")
+            .unwrap();
+        let arity = compiled
+            .iter()
+            .flat_map(|prog| prog.values())
+            .map(|rule_set| rule_set.arity())
+            .next()
+            .unwrap();
+        assert_eq!(arity, 2);
+
+        let err = compiler
+            .compile_script("?[a, b] <~ Limit(*r[a, b], limit: -1)")
+            .unwrap_err();
+        assert!(err.downcast_ref::<WrongFixedRuleOptionError>().is_some());
+        assert!(compiler
+            .compile_script("?[a, b] <~ Limit(*r[a, b])")
+            .is_err());
+    }
+
+    #[test]
+    fn limit_rejects_head_narrower_or_wider_than_input() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create r {a: Int, b: Int}").unwrap();
+        for script in [
+            "?[a] <~ Limit(*r[a, b], limit: 1)",
+            "?[a, b, c] <~ Limit(*r[a, b], limit: 1)",
+        ] {
+            let err = compiler.compile_script(script).unwrap_err();
+            let mismatch = err.downcast_ref::<HeadArityMismatchError>().unwrap();
+            assert_eq!(mismatch.2, 2);
+        }
+        let err = compiler
+            .compile_script("?[a] <~ Limit(*r[a, b], *r[c, d], limit: 1)")
+            .unwrap_err();
+        assert!(err.downcast_ref::<WrongInputRelationCountError>().is_some());
+    }
+}
@@ -7,6 +7,12 @@
  */
 
 pub(crate) mod constant;
+pub(crate) mod distinct;
+pub(crate) mod limit;
 pub(crate) mod reorder_sort;
+pub(crate) mod union;
 
 pub(crate) use constant::Constant;
+pub use distinct::Distinct;
+pub use limit::Limit;
+pub use union::Union;
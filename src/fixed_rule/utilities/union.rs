@@ -0,0 +1,170 @@
+/*
+ * Copyright 2022, The Cozo Project Authors.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0.
+ * If a copy of the MPL was not distributed with this file,
+ * You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+
+use miette::{bail, ensure, Diagnostic, Result};
+use thiserror::Error;
+
+use crate::compile::expr::Expr;
+use crate::compile::symb::Symbol;
+use crate::fixed_rule::{
+    CannotDetermineArity, FixedRule, HeadArityMismatchError, WrongInputRelationCountError,
+};
+use crate::parse::SourceSpan;
+use crate::runtime::db::NamedRows;
+
+/// Concatenates the rows of all its input relations, which must have the same arity.
+/// Duplicates are kept; follow with `Distinct` for a set union.
+#[derive(Debug)]
+pub struct Union;
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("Input relations to 'Union' must have the same arity")]
+#[diagnostic(code(fixed_rule::union_arity_mismatch))]
+#[diagnostic(help("The first input has arity {0}, but input {1} has arity {2}"))]
+pub(crate) struct UnionArityMismatchError(
+    pub(crate) usize,
+    pub(crate) usize,
+    pub(crate) usize,
+    #[label] pub(crate) SourceSpan,
+);
+
+impl Union {
+    /// Applies the rule to its realized input relations, whose arities have been checked
+    /// when the rule application was compiled.
+    pub fn run(&self, inputs: Vec<NamedRows>, span: SourceSpan) -> Result<NamedRows> {
+        let mut inputs = inputs.into_iter();
+        let mut out = inputs
+            .next()
+            .ok_or_else(|| WrongInputRelationCountError("Union".to_string(), 1, 0, span))?;
+        out.next = None;
+        for input in inputs {
+            out.rows.extend(input.rows);
+        }
+        Ok(out)
+    }
+}
+
+impl FixedRule for Union {
+    fn arity(
+        &self,
+        _options: &BTreeMap<String, Expr>,
+        rule_head: &[Symbol],
+        span: SourceSpan,
+    ) -> Result<usize> {
+        if rule_head.is_empty() {
+            bail!(CannotDetermineArity(
+                "Union".to_string(),
+                "the rule head is not given".to_string(),
+                span
+            ))
+        }
+        Ok(rule_head.len())
+    }
+
+    fn check_input_arities(
+        &self,
+        arities: &[usize],
+        out_arity: usize,
+        span: SourceSpan,
+    ) -> Result<()> {
+        let Some((first, rest)) = arities.split_first() else {
+            bail!(WrongInputRelationCountError("Union".to_string(), 1, 0, span))
+        };
+        for (idx, arity) in rest.iter().enumerate() {
+            ensure!(
+                arity == first,
+                UnionArityMismatchError(*first, idx + 1, *arity, span)
+            );
+        }
+        ensure!(
+            *first == out_arity,
+            HeadArityMismatchError("Union".to_string(), out_arity, *first, span)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::Compiler;
+    use crate::data::value::DataValue;
+
+    fn rows(headers: &[&str], rows: Vec<Vec<i64>>) -> NamedRows {
+        NamedRows::new(
+            headers.iter().map(|h| h.to_string()).collect(),
+            rows.into_iter()
+                .map(|row| row.into_iter().map(DataValue::from).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn union_concatenates_inputs() {
+        let span = SourceSpan(2, 5);
+        let out = Union
+            .run(
+                vec![
+                    rows(&["a", "b"], vec![vec![1, 2], vec![3, 4]]),
+                    rows(&["x", "y"], vec![vec![1, 2], vec![5, 6]]),
+                ],
+                span,
+            )
+            .unwrap();
+        assert_eq!(out.headers, vec!["a", "b"]);
+        assert_eq!(
+            out.rows,
+            rows(&[], vec![vec![1, 2], vec![3, 4], vec![1, 2], vec![5, 6]]).rows
+        );
+        assert!(Union.run(vec![], span).is_err());
+    }
+
+    #[test]
+    fn union_rejects_mismatched_arity() {
+        let span = SourceSpan(2, 5);
+        assert!(Union.check_input_arities(&[2, 2, 2], 2, span).is_ok());
+        let err = Union.check_input_arities(&[2, 2, 1], 2, span).unwrap_err();
+        let mismatch = err.downcast_ref::<UnionArityMismatchError>().unwrap();
+        assert_eq!((mismatch.0, mismatch.1, mismatch.2, mismatch.3), (2, 2, 1, span));
+        assert!(Union
+            .check_input_arities(&[], 2, span)
+            .unwrap_err()
+            .downcast_ref::<WrongInputRelationCountError>()
+            .is_some());
+        let err = Union.check_input_arities(&[2, 2], 1, span).unwrap_err();
+        let mismatch = err.downcast_ref::<HeadArityMismatchError>().unwrap();
+        assert_eq!((mismatch.1, mismatch.2), (1, 2));
+    }
+
+    #[test]
+    fn union_checks_input_arities_when_compiling() {
+        let mut compiler = Compiler::new();
+        compiler.compile_script(":create r {a: Int, b: Int}").unwrap();
+        compiler.compile_script(":create s {a: Int}").unwrap();
+        let script = "t[a, b] := a = 1, b = 2
+                      ?[a, b] <~ Union(*r[a, b], t[c, d])";
+        assert!(compiler.compile_script(script).is_ok());
+
+        let script = "t[a] := a = 1
+                      ?[a, b] <~ Union(*r[a, b], t[c])";
+        let err = compiler.compile_script(script).unwrap_err();
+        let mismatch = err.downcast_ref::<UnionArityMismatchError>().unwrap();
+        assert_eq!((mismatch.0, mismatch.1, mismatch.2), (2, 1, 1));
+        let err = compiler
+            .compile_script("?[a, b] <~ Union(*s[a], *r[b, c])")
+            .unwrap_err();
+        let mismatch = err.downcast_ref::<UnionArityMismatchError>().unwrap();
+        assert_eq!((mismatch.0, mismatch.1, mismatch.2), (1, 1, 2));
+        let err = compiler
+            .compile_script("?[a] <~ Union(*r[a, b], *r[c, d])")
+            .unwrap_err();
+        assert!(err.downcast_ref::<HeadArityMismatchError>().is_some());
+    }
+}
@@ -47,6 +47,15 @@ use miette::{
 };
 use parse::SourceSpan;
 pub use crate::compile::Compiler;
+pub use crate::compile::{CompiledProgram, CompiledRuleSet};
+pub use crate::compile::program::MagicSymbol;
+pub use crate::diagnostics::explain::{
+    explain_compiled, explain_compiled_json, explain_compiled_verbose,
+};
+pub use crate::runtime::db::NamedRows;
+pub use crate::fixed_rule::utilities::{Distinct, Limit, Union};
+pub use crate::compile::stratify::{RuleDependency, StratificationInfo};
+pub use crate::data::functions::set_rand_seed;
 use serde_json::json;
 
 use crate::compile::symb::Symbol;
@@ -12,6 +12,7 @@ use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 use either::{Either, Left};
+use log::debug;
 use miette::{bail, Diagnostic, IntoDiagnostic, Result};
 use pest::error::InputLocation;
 use pest::Parser;
@@ -275,6 +276,7 @@ pub(crate) fn parse_expressions(
 
 pub fn parse_script(
     src: &str,
+    param_pool: &BTreeMap<String, DataValue>,
     fixed_rules: &BTreeMap<String, Arc<Box<dyn FixedRule>>>,
 ) -> Result<CozoScript> {
     let parsed = CozoScriptParser::parse(Rule::script, src)
@@ -289,8 +291,8 @@ pub fn parse_script(
         .unwrap();
     Ok(match parsed.as_rule() {
         Rule::query_script => {
-            let q = parse_query(parsed.into_inner(), fixed_rules)?;
-            println!("xxx295 q= {q:?}");
+            let q = parse_query(parsed.into_inner(), param_pool, fixed_rules)?;
+            debug!("parsed query: {q:?}");
             // let temp_rules = match &q.prog[&Symbol::new("fibo", SourceSpan(0,0))] {
             //     InputInlineRulesOrFixed::Rules { rules } => &rules[1].body[0],
             //     InputInlineRulesOrFixed::Fixed { fixed } => todo!(),
@@ -299,12 +301,13 @@ pub fn parse_script(
             CozoScript::Single(q)
         }
         Rule::imperative_script => {
-            let p = parse_imperative_block(parsed, fixed_rules)?;
+            let p = parse_imperative_block(parsed, param_pool, fixed_rules)?;
             CozoScript::Imperative(p)
         }
 
         Rule::sys_script => CozoScript::Sys(parse_sys(
             parsed.into_inner(),
+            param_pool,
             fixed_rules,
         )?),
         _ => unreachable!(),
@@ -67,9 +67,9 @@ struct ProcessIdError(String, #[label] SourceSpan);
 
 pub(crate) fn parse_sys(
     mut src: Pairs<'_>,
+    param_pool: &BTreeMap<String, DataValue>,
     algorithms: &BTreeMap<String, Arc<Box<dyn FixedRule>>>,
 ) -> Result<SysOp> {
-    let param_pool: &BTreeMap<String, DataValue> = &BTreeMap::new();
     let inner = src.next().unwrap();
     Ok(match inner.as_rule() {
         Rule::compact_op => SysOp::Compact,
@@ -86,6 +86,7 @@ pub(crate) fn parse_sys(
         Rule::explain_op => {
             let prog = parse_query(
                 inner.into_inner().next().unwrap().into_inner(),
+                param_pool,
                 algorithms,
             )?;
             SysOp::Explain(Box::new(prog))
@@ -168,6 +169,7 @@ pub(crate) fn parse_sys(
                 let script_str = script.as_str();
                 parse_query(
                     script.into_inner(),
+                    param_pool,
                     algorithms,
                 )?;
                 match op.as_rule() {
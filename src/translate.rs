@@ -24,13 +24,13 @@ pub fn translate_relation(relation: &RelAlgebra) -> DiffdafRelation {
         crate::compile::RelAlgebra::TempStore(_) => todo!(),
         crate::compile::RelAlgebra::Stored(_) => todo!(),
         crate::compile::RelAlgebra::Join(  b) => {
-            let InnerJoin{ left, right, joiner, to_eliminate, span } = (**b).clone();
+            let InnerJoin{ left, right, joiner, to_eliminate, span, .. } = (**b).clone();
 
             if let RelAlgebra::Fixed(InlineFixedRA{ bindings, data, to_eliminate, span }) = left{
                 if data == vec![vec![]] {
                     // this is Fixed Unit rule join??? workaround we need to understand
 
-                    if let RelAlgebra::Stored(StoredRA{ bindings, filters, span, name }) = right {
+                    if let RelAlgebra::Stored(StoredRA{ bindings, filters, span, name, .. }) = right {
                         DiffdafRelation::Predicate(name)
                     } else if let RelAlgebra::TempStore(TempStoreRA{ bindings, storage_key, filters, span }) = right {
                         DiffdafRelation::Predicate(storage_key.to_string())